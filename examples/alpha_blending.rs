@@ -6,7 +6,7 @@ const WIDTH: u32 = 256;
 const HEIGHT: u32 = 256;
 
 impl Callbacks for Game {
-    fn init(&self, ctx: &mut Context) {
+    fn init(&mut self, ctx: &mut Context) {
         canvas::resize(ctx, WIDTH, HEIGHT);
     }
 
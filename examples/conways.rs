@@ -87,10 +87,6 @@ impl Conways {
 }
 
 impl Callbacks for Conways {
-    fn init(&self, ctx: &mut Context) {
-        canvas::resize(ctx, WIDTH, HEIGHT);
-    }
-
     fn update(&mut self, ctx: &mut Context) -> bool {
         let mouse_pos = input::mouse_pos_pixel(ctx);
 
@@ -130,5 +126,9 @@ fn main() {
     println!("Space: to advance");
     println!("LMB: to place");
     println!("RMB: to remove");
-    pixelated::run(app)
+    pixelated::App::new()
+        .title("Conways")
+        .canvas_size(WIDTH, HEIGHT)
+        .window_scale(10)
+        .run(app)
 }
@@ -6,7 +6,7 @@ const HEIGHT: u32 = 256;
 struct Game {}
 
 impl Callbacks for Game {
-    fn init(&self, ctx: &mut Context) {
+    fn init(&mut self, ctx: &mut Context) {
         canvas::resize(ctx, WIDTH, HEIGHT);
         window::window_ref(ctx).set_resizable(true);
     }
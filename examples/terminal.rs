@@ -0,0 +1,35 @@
+use pixelated::{canvas, input, terminal, Callbacks, Context};
+
+struct Game {
+    x: u32,
+    y: u32,
+}
+
+impl Callbacks for Game {
+    fn update(&mut self, ctx: &mut Context) -> bool {
+        let (width, height) = (canvas::width(ctx), canvas::height(ctx));
+
+        if input::key_pressed(ctx, input::KeyCode::ArrowLeft) {
+            self.x = self.x.saturating_sub(1);
+        }
+        if input::key_pressed(ctx, input::KeyCode::ArrowRight) {
+            self.x = (self.x + 1).min(width - 1);
+        }
+        if input::key_pressed(ctx, input::KeyCode::ArrowUp) {
+            self.y = self.y.saturating_sub(1);
+        }
+        if input::key_pressed(ctx, input::KeyCode::ArrowDown) {
+            self.y = (self.y + 1).min(height - 1);
+        }
+
+        canvas::clear_screen(ctx, &[0, 0, 64]);
+        canvas::write_pixel_rgb(ctx, self.x, self.y, &[255, 255, 0]);
+
+        input::key_just_pressed(ctx, input::KeyCode::Escape)
+    }
+}
+
+fn main() {
+    let app = Game { x: 0, y: 0 };
+    terminal::run(app);
+}
@@ -0,0 +1,403 @@
+// Named actions and axes bound to keyboard, mouse and gamepad input
+
+use crate::{
+    config::ConfigError,
+    input::{self, GamepadAxis, GamepadButton, KeyCode, MouseButton},
+    Context,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::Path};
+
+/// Input triggering an action
+///
+/// Gamepad bindings match any connected gamepad
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButton),
+}
+
+/// Input driving an axis in range [-1,1]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AxisBinding {
+    /// -1 while negative is held, 1 while positive is held
+    Keys {
+        negative: KeyCode,
+        positive: KeyCode,
+    },
+    /// -1 while negative is held, 1 while positive is held
+    GamepadButtons {
+        negative: GamepadButton,
+        positive: GamepadButton,
+    },
+    /// Analog gamepad axis with dead zones applied
+    Gamepad(GamepadAxis),
+}
+
+/// Bindings of all actions and axes
+///
+/// Stored as TOML, e.g.
+/// ```toml
+/// [actions]
+/// jump = [{ key = "Space" }, { gamepad = "South" }]
+///
+/// [axes]
+/// move_x = [{ keys = { negative = "KeyA", positive = "KeyD" } }, { gamepad = "LeftStickX" }]
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Bindings {
+    pub actions: BTreeMap<String, Vec<Binding>>,
+    pub axes: BTreeMap<String, Vec<AxisBinding>>,
+}
+
+impl Bindings {
+    /// Load bindings from a TOML file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let contents = std::fs::read_to_string(path).map_err(ConfigError::Io)?;
+        Self::from_toml(&contents)
+    }
+
+    /// Parse bindings from TOML
+    pub fn from_toml(contents: &str) -> Result<Self, ConfigError> {
+        toml::from_str(contents).map_err(ConfigError::Parse)
+    }
+
+    /// Serialize bindings to TOML
+    pub fn to_toml(&self) -> Result<String, ConfigError> {
+        toml::to_string_pretty(self).map_err(ConfigError::Serialize)
+    }
+
+    /// Save bindings to a TOML file
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ConfigError> {
+        std::fs::write(path, self.to_toml()?).map_err(ConfigError::Io)
+    }
+}
+
+impl Binding {
+    fn pressed(&self, ctx: &Context) -> bool {
+        match *self {
+            Binding::Key(key) => input::key_pressed(ctx, key),
+            Binding::Mouse(button) => input::mouse_button_pressed(ctx, button),
+            Binding::Gamepad(button) => {
+                input::gamepads(ctx).any(|slot| input::gamepad_button_pressed(ctx, slot, button))
+            }
+        }
+    }
+
+    /// If the binding was held last frame
+    fn was_pressed(&self, ctx: &Context) -> bool {
+        match *self {
+            Binding::Key(key) => {
+                (input::key_pressed(ctx, key) && !input::key_just_pressed(ctx, key))
+                    || input::key_released(ctx, key)
+            }
+            Binding::Mouse(button) => {
+                (input::mouse_button_pressed(ctx, button)
+                    && !input::mouse_button_just_pressed(ctx, button))
+                    || input::mouse_button_released(ctx, button)
+            }
+            Binding::Gamepad(button) => input::gamepads(ctx).any(|slot| {
+                (input::gamepad_button_pressed(ctx, slot, button)
+                    && !input::gamepad_button_just_pressed(ctx, slot, button))
+                    || input::gamepad_button_released(ctx, slot, button)
+            }),
+        }
+    }
+}
+
+impl AxisBinding {
+    fn value(&self, ctx: &Context) -> f32 {
+        let direction =
+            |negative: bool, positive: bool| positive as i8 as f32 - negative as i8 as f32;
+        match *self {
+            AxisBinding::Keys { negative, positive } => direction(
+                input::key_pressed(ctx, negative),
+                input::key_pressed(ctx, positive),
+            ),
+            AxisBinding::GamepadButtons { negative, positive } => input::gamepads(ctx)
+                .map(|slot| {
+                    direction(
+                        input::gamepad_button_pressed(ctx, slot, negative),
+                        input::gamepad_button_pressed(ctx, slot, positive),
+                    )
+                })
+                .fold(0.0, strongest),
+            AxisBinding::Gamepad(axis) => input::gamepads(ctx)
+                .map(|slot| input::gamepad_axis(ctx, slot, axis))
+                .fold(0.0, strongest),
+        }
+    }
+}
+
+/// Value furthest from zero
+fn strongest(a: f32, b: f32) -> f32 {
+    if b.abs() > a.abs() {
+        b
+    } else {
+        a
+    }
+}
+
+//
+// Commands
+//
+
+/// If any input bound to action is pressed
+///
+/// Unknown actions are never pressed
+pub fn action_pressed(ctx: &Context, action: &str) -> bool {
+    bindings(ctx, action)
+        .iter()
+        .any(|binding| binding.pressed(ctx))
+}
+
+/// If action became pressed this frame
+///
+/// Pressing a second bound input while holding the first does not count
+pub fn action_just_pressed(ctx: &Context, action: &str) -> bool {
+    let bindings = bindings(ctx, action);
+    bindings.iter().any(|binding| binding.pressed(ctx))
+        && !bindings.iter().any(|binding| binding.was_pressed(ctx))
+}
+
+/// If action stopped being pressed this frame
+pub fn action_released(ctx: &Context, action: &str) -> bool {
+    let bindings = bindings(ctx, action);
+    !bindings.iter().any(|binding| binding.pressed(ctx))
+        && bindings.iter().any(|binding| binding.was_pressed(ctx))
+}
+
+/// Value of axis in range [-1,1]
+///
+/// The binding furthest from zero wins, unknown axes are 0.0
+pub fn axis_value(ctx: &Context, axis: &str) -> f32 {
+    ctx.config
+        .bindings
+        .axes
+        .get(axis)
+        .into_iter()
+        .flatten()
+        .map(|binding| binding.value(ctx))
+        .fold(0.0, strongest)
+}
+
+/// Inputs bound to action
+pub fn bindings<'a>(ctx: &'a Context, action: &str) -> &'a [Binding] {
+    ctx.config
+        .bindings
+        .actions
+        .get(action)
+        .map(Vec::as_slice)
+        .unwrap_or(&[])
+}
+
+/// Adds an input to action
+pub fn bind(ctx: &mut Context, action: &str, binding: Binding) {
+    let bindings = ctx
+        .config
+        .bindings
+        .actions
+        .entry(action.to_string())
+        .or_default();
+    if !bindings.contains(&binding) {
+        bindings.push(binding);
+    }
+}
+
+/// Removes an input from action
+pub fn unbind(ctx: &mut Context, action: &str, binding: Binding) {
+    if let Some(bindings) = ctx.config.bindings.actions.get_mut(action) {
+        bindings.retain(|b| *b != binding);
+    }
+}
+
+/// Replaces all inputs bound to action
+pub fn rebind(ctx: &mut Context, action: &str, bindings: Vec<Binding>) {
+    ctx.config
+        .bindings
+        .actions
+        .insert(action.to_string(), bindings);
+}
+
+/// Adds an input to axis
+pub fn bind_axis(ctx: &mut Context, axis: &str, binding: AxisBinding) {
+    let bindings = ctx
+        .config
+        .bindings
+        .axes
+        .entry(axis.to_string())
+        .or_default();
+    if !bindings.contains(&binding) {
+        bindings.push(binding);
+    }
+}
+
+/// Replaces all inputs bound to axis
+pub fn rebind_axis(ctx: &mut Context, axis: &str, bindings: Vec<AxisBinding>) {
+    ctx.config.bindings.axes.insert(axis.to_string(), bindings);
+}
+
+/// All action and axis bindings
+///
+/// Initially the bindings in the configuration, saved with it by [`crate::config::save`]
+pub fn current(ctx: &Context) -> &Bindings {
+    &ctx.config.bindings
+}
+
+/// Replaces all action and axis bindings
+pub fn set_bindings(ctx: &mut Context, bindings: Bindings) {
+    ctx.config.bindings = bindings;
+}
+
+/// Save all action and axis bindings to a TOML file
+pub fn save(ctx: &Context, path: impl AsRef<Path>) -> Result<(), ConfigError> {
+    ctx.config.bindings.save(path)
+}
+
+/// Replace all action and axis bindings with those in a TOML file
+pub fn load(ctx: &mut Context, path: impl AsRef<Path>) -> Result<(), ConfigError> {
+    ctx.config.bindings = Bindings::load(path)?;
+    Ok(())
+}
+
+//
+// Tests
+//
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        action_just_pressed, action_pressed, action_released, axis_value, bind, bind_axis, current,
+        rebind, AxisBinding, Binding, Bindings,
+    };
+    use crate::{
+        config::{self, AppConfig},
+        input::{GamepadAxis, GamepadButton, GamepadEvent, KeyCode, MouseButton},
+        Context,
+    };
+
+    #[test]
+    fn test_action_state() {
+        let mut ctx = Context::headless(8, 8);
+        bind(&mut ctx, "jump", Binding::Key(KeyCode::Space));
+        bind(&mut ctx, "jump", Binding::Key(KeyCode::KeyW));
+
+        ctx.input.keyboard.set_key(KeyCode::Space);
+        assert!(action_pressed(&ctx, "jump"));
+        assert!(action_just_pressed(&ctx, "jump"));
+
+        // Pressing a second bound key while holding the first
+        ctx.input.keyboard.save_keys();
+        ctx.input.keyboard.set_key(KeyCode::KeyW);
+        assert!(!action_just_pressed(&ctx, "jump"));
+
+        ctx.input.keyboard.save_keys();
+        ctx.input.keyboard.release_key(KeyCode::Space);
+        ctx.input.keyboard.release_key(KeyCode::KeyW);
+        assert!(!action_pressed(&ctx, "jump"));
+        assert!(action_released(&ctx, "jump"));
+        assert!(!action_pressed(&ctx, "unknown"));
+    }
+
+    #[test]
+    fn test_axis_value() {
+        let mut ctx = Context::headless(8, 8);
+        bind_axis(
+            &mut ctx,
+            "move_x",
+            AxisBinding::Keys {
+                negative: KeyCode::KeyA,
+                positive: KeyCode::KeyD,
+            },
+        );
+        bind_axis(
+            &mut ctx,
+            "move_x",
+            AxisBinding::Gamepad(GamepadAxis::LeftStickX),
+        );
+
+        ctx.input.keyboard.set_key(KeyCode::KeyA);
+        assert_eq!(axis_value(&ctx, "move_x"), -1.0);
+
+        ctx.input.keyboard.set_key(KeyCode::KeyD);
+        assert_eq!(axis_value(&ctx, "move_x"), 0.0);
+
+        ctx.input.gamepad.handle_event(&GamepadEvent::Connected {
+            id: 0,
+            name: String::new(),
+        });
+        ctx.input.gamepad.handle_event(&GamepadEvent::AxisChanged {
+            id: 0,
+            axis: GamepadAxis::LeftStickX,
+            value: 1.0,
+        });
+        assert_eq!(axis_value(&ctx, "move_x"), 1.0);
+    }
+
+    #[test]
+    fn test_toml_round_trip() {
+        let mut bindings = Bindings::default();
+        bindings.actions.insert(
+            String::from("jump"),
+            vec![
+                Binding::Key(KeyCode::Space),
+                Binding::Mouse(MouseButton::Left),
+                Binding::Gamepad(GamepadButton::South),
+            ],
+        );
+        bindings.axes.insert(
+            String::from("move_x"),
+            vec![
+                AxisBinding::Keys {
+                    negative: KeyCode::KeyA,
+                    positive: KeyCode::KeyD,
+                },
+                AxisBinding::Gamepad(GamepadAxis::LeftStickX),
+            ],
+        );
+
+        let toml = bindings.to_toml().unwrap();
+        assert_eq!(Bindings::from_toml(&toml).unwrap(), bindings);
+    }
+
+    #[test]
+    fn test_parse_documented_format() {
+        let bindings = Bindings::from_toml(
+            r#"
+            [actions]
+            jump = [{ key = "Space" }, { gamepad = "South" }]
+
+            [axes]
+            move_x = [{ keys = { negative = "KeyA", positive = "KeyD" } }]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            bindings.actions["jump"][1],
+            Binding::Gamepad(GamepadButton::South)
+        );
+        assert_eq!(
+            bindings.axes["move_x"],
+            [AxisBinding::Keys {
+                negative: KeyCode::KeyA,
+                positive: KeyCode::KeyD
+            }]
+        );
+    }
+
+    #[test]
+    fn test_config_saves_rebound_actions() {
+        let mut ctx = Context::headless(8, 8);
+        rebind(&mut ctx, "fire", vec![Binding::Key(KeyCode::KeyF)]);
+
+        let saved = config::current(&ctx).to_toml().unwrap();
+        let saved = AppConfig::from_toml(&saved).unwrap();
+
+        assert_eq!(saved.bindings, *current(&ctx));
+    }
+}
@@ -1,7 +1,17 @@
 use crate::{
-    context::Context, input::InputContext, render::RenderContext, time::TimeContext, window,
+    combo::{self, ComboContext},
+    config::AppConfig,
+    context::Context,
+    debug::{self, DebugContext},
+    input::{self, InputContext, KeyCode},
+    profile::ProfileContext,
+    render::RenderContext,
+    terminal,
+    time::{self, TimeContext},
+    window,
 };
-use winit::event_loop::EventLoop;
+use std::{path::PathBuf, time::Instant};
+use winit::{event::WindowEvent, event_loop::EventLoop};
 
 /// User callbacks
 ///
@@ -9,7 +19,7 @@ use winit::event_loop::EventLoop;
 pub trait Callbacks {
     /// Called before initalization
     #[allow(unused_variables)]
-    fn init(&self, ctx: &mut Context) {}
+    fn init(&mut self, ctx: &mut Context) {}
 
     /// Called once per frame before render
     ///
@@ -18,16 +28,172 @@ pub trait Callbacks {
     fn update(&mut self, ctx: &mut Context) -> bool {
         false
     }
+
+    /// Called zero or more times per frame before update at the fixed timestep
+    ///
+    /// Only called when a fixed timestep is set with time::set_fixed_timestep.
+    /// time::delta_time is the fixed timestep here. Input just pressed or released since
+    /// the last fixed update is seen by the first of the frame's fixed updates, even if
+    /// it happened on a frame without one.
+    /// Events, text input, mouse delta and key repeats are per frame: every fixed update
+    /// of a frame sees them, and frames without a fixed update do not carry them over
+    ///
+    /// Return value determines wether to exit game or not
+    #[allow(unused_variables)]
+    fn fixed_update(&mut self, ctx: &mut Context) -> bool {
+        false
+    }
+
+    /// Called once per frame after update
+    ///
+    /// Alpha is how far the frame is between the previous and next fixed update \[0,1\],
+    /// useful for interpolating positions
+    #[allow(unused_variables)]
+    fn render(&self, ctx: &mut Context, alpha: f32) {}
+
+    /// Called when the window or terminal is resized
+    ///
+    /// Size is in physical pixels, or in the terminal its columns and twice its rows
+    #[allow(unused_variables)]
+    fn on_resize(&mut self, ctx: &mut Context, width: u32, height: u32) {}
+
+    /// Called when the window gains or loses focus
+    #[allow(unused_variables)]
+    fn on_focus_changed(&mut self, ctx: &mut Context, focused: bool) {}
+
+    /// Called once before the event loop ends
+    ///
+    /// Called however the app exits, useful for saving
+    #[allow(unused_variables)]
+    fn on_exit(&mut self, ctx: &mut Context) {}
+
+    /// Called when a file is dropped on the window
+    #[allow(unused_variables)]
+    fn on_file_dropped(&mut self, ctx: &mut Context, path: PathBuf) {}
+
+    /// Called with every window event before it is handled
+    #[allow(unused_variables)]
+    fn on_window_event(&mut self, ctx: &mut Context, event: &WindowEvent) {}
+}
+
+/// Configures and runs the application
+///
+/// ```no_run
+/// # struct Game;
+/// # impl pixelated::Callbacks for Game {}
+/// pixelated::App::new()
+///     .title("game")
+///     .canvas_size(128, 128)
+///     .window_scale(3)
+///     .vsync(false)
+///     .run(Game);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct App {
+    config: AppConfig,
+}
+
+impl App {
+    /// App with default configuration
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// App with the specified configuration
+    pub fn from_config(config: AppConfig) -> Self {
+        Self { config }
+    }
+
+    /// Window title
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.config.title = title.into();
+        self
+    }
+
+    /// Initial logical window size, overrides window_scale
+    pub fn window_size(mut self, width: u32, height: u32) -> Self {
+        self.config.window_size = Some((width, height));
+        self
+    }
+
+    /// Initial canvas size in pixels
+    pub fn canvas_size(mut self, width: u32, height: u32) -> Self {
+        self.config.canvas_size = (width, height);
+        self
+    }
+
+    /// Initial window size as a multiple of the canvas size
+    pub fn window_scale(mut self, scale: u32) -> Self {
+        self.config.window_scale = Some(scale);
+        self
+    }
+
+    /// If the window can be resized
+    pub fn resizable(mut self, resizable: bool) -> Self {
+        self.config.resizable = resizable;
+        self
+    }
+
+    /// If presentation waits for vsync
+    pub fn vsync(mut self, vsync: bool) -> Self {
+        self.config.vsync = vsync;
+        self
+    }
+
+    /// Path to a png or jpeg used as window icon
+    pub fn icon(mut self, path: impl Into<String>) -> Self {
+        self.config.icon = Some(path.into());
+        self
+    }
+
+    /// Key toggling the debug overlay
+    pub fn debug_overlay_key(mut self, key: KeyCode) -> Self {
+        self.config.debug_overlay_key = Some(key);
+        self
+    }
+
+    /// Current configuration
+    pub fn config(&self) -> &AppConfig {
+        &self.config
+    }
+
+    /// Runs the event loop
+    ///
+    /// Calls user defined functions thorugh Callback trait
+    pub fn run<C>(self, callbacks: C)
+    where
+        C: Callbacks + 'static,
+    {
+        env_logger::init();
+        let mut app = Runner { callbacks };
+
+        let (mut ctx, event_loop) = pollster::block_on(build_context(&self.config));
+
+        app.callbacks.init(&mut ctx);
+
+        pollster::block_on(window::run_window(event_loop, app, ctx));
+    }
+
+    /// Runs the application in the terminal instead of a window
+    ///
+    /// Uses the canvas size, bindings and debug overlay key of the configuration,
+    /// see [`crate::terminal::run`]
+    pub fn run_terminal<C>(self, callbacks: C)
+    where
+        C: Callbacks + 'static,
+    {
+        terminal::run_with_config(callbacks, self.config);
+    }
 }
 
-/// Main App
+/// Runner
 /// Contains all data to run application
-pub(crate) struct App<C: Callbacks> {
+pub(crate) struct Runner<C: Callbacks> {
     pub(crate) callbacks: C,
 }
 
-/// Functions implemented on App
-impl<C> App<C>
+/// Functions implemented on Runner
+impl<C> Runner<C>
 where
     C: Callbacks + 'static,
 {
@@ -37,48 +203,114 @@ where
         // Update time
         ctx.time.update_time();
 
+        // Poll gamepads
+        ctx.input.poll_gamepads();
+
+        // Mouse gestures
+        input::update_gestures(ctx);
+
+        // Key repeat
+        let delta_time = time::real_delta_time(ctx);
+        ctx.input.keyboard.update_repeat(delta_time);
+
+        // Input buffer for combos
+        combo::update_buffer(ctx);
+
+        // Timer callbacks
+        for (timer, times, mut callback) in ctx.time.take_fired_callbacks() {
+            for _ in 0..times {
+                // The callback may cancel its own timer
+                if !ctx.time.has_timer(timer) {
+                    break;
+                }
+                callback(ctx);
+            }
+            ctx.time.restore_callback(timer, callback);
+        }
+
+        // Fixed update callback
+        // Just pressed and released compare against the last fixed update that ran,
+        // and are only seen by the first step of the frame
+        let steps = ctx.time.fixed_steps();
+        if steps > 0 {
+            ctx.time.set_in_fixed_update(true);
+            let frame_previous = ctx.input.begin_fixed_update();
+            let mut exit = false;
+            for step in 0..steps {
+                if step > 0 {
+                    ctx.input.latch();
+                }
+                let start = Instant::now();
+                exit = self.callbacks.fixed_update(ctx);
+                ctx.profile.record("fixed_update", start, start.elapsed());
+                if exit {
+                    break;
+                }
+            }
+            ctx.input.end_fixed_update(frame_previous);
+            ctx.time.set_in_fixed_update(false);
+            if exit {
+                return true;
+            }
+        }
+
         // Update callback
-        if self.callbacks.update(ctx) {
+        let start = Instant::now();
+        let exit = self.callbacks.update(ctx);
+        ctx.profile.record("update", start, start.elapsed());
+        if exit {
             return true;
         }
 
+        // Render callback
+        let alpha = ctx.time.fixed_alpha();
+        let start = Instant::now();
+        self.callbacks.render(ctx, alpha);
+        ctx.profile.record("render", start, start.elapsed());
+
+        // Overlays
+        debug::update_overlay(ctx);
+        window::draw_cursor(ctx);
+
         // Reset input
         ctx.input.keyboard.save_keys();
         ctx.input.keyboard.save_modifiers();
         ctx.input.mouse.save_buttons();
         ctx.input.mouse.set_mouse_delta((0.0, 0.0));
+        ctx.input.gamepad.save_buttons();
+        ctx.input.touch.save_touches();
+        ctx.input.text.clear();
+        ctx.input.events.clear();
 
         false
     }
 }
 
-/// Runs the event loop
+/// Runs the event loop with the default configuration
 ///
 /// Calls user defined functions thorugh Callback trait
+#[allow(clippy::multiple_bound_locations)]
 pub fn run<C: Callbacks + 'static>(callbacks: C)
 where
     C: Callbacks + 'static,
 {
-    env_logger::init();
-    let app = App { callbacks };
-
-    let (mut ctx, event_loop) = pollster::block_on(build_context());
-
-    app.callbacks.init(&mut ctx);
-
-    pollster::block_on(window::run_window(event_loop, app, ctx));
+    App::new().run(callbacks);
 }
 
-async fn build_context() -> (Context, EventLoop<()>) {
-    let (window, event_loop) = window::new_window();
+async fn build_context(config: &AppConfig) -> (Context, EventLoop<()>) {
+    let (window, event_loop) = window::new_window(config);
 
     let time = TimeContext::default();
     let input = InputContext::default();
-    let render = RenderContext::new(window).await;
+    let render = RenderContext::new(window, config.canvas_size, config.vsync).await;
     let context = Context {
         render,
         time,
         input,
+        config: config.clone(),
+        profile: ProfileContext::default(),
+        debug: DebugContext::default(),
+        combo: ComboContext::default(),
     };
 
     (context, event_loop)
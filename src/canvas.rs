@@ -4,17 +4,24 @@ pub(crate) const DEFAULT_CLEAR_COLOR: [u8; 3] = [0, 0, 0]; // Black
 pub(crate) const DEFAULT_CANVAS_WIDTH: u32 = 512;
 pub(crate) const DEFAULT_CANVAS_HEIGHT: u32 = 512;
 
-/// Represent the screen of pixels
-pub(crate) struct Canvas {
+/// Grid of RGBA pixels
+///
+/// The context owns the canvas presented each frame, additional canvases can be
+/// drawn into offscreen and composited onto it with [`draw_canvas`]
+#[derive(Clone)]
+pub struct Canvas {
     pub(crate) pixels: Vec<u8>,
     pub(crate) width: u32,
     pub(crate) height: u32,
     last_clear_color: [u8; 3],
+    // intersected clip rectangles as (x0, y0, x1, y1), x1 and y1 exclusive
+    clip_stack: Vec<(u32, u32, u32, u32)>,
+    mask: Option<Mask>,
 }
 
 impl Canvas {
     /// Create new canvas with specified width and height
-    pub(crate) fn new(width: u32, height: u32) -> Self {
+    pub fn new(width: u32, height: u32) -> Self {
         let capacity = width * height * 4;
         let pixels = vec![0; capacity as usize];
         let last_clear_color = DEFAULT_CLEAR_COLOR;
@@ -23,20 +30,25 @@ impl Canvas {
             width,
             height,
             last_clear_color,
+            clip_stack: Vec::new(),
+            mask: None,
         }
     }
 
     /// Resizes the canvas
-    /// Clears screen to ```clear_color```
-    pub(crate) fn resize(&mut self, width: u32, height: u32) {
+    /// Clears screen to ```clear_color``` and removes all clip rectangles
+    pub fn resize(&mut self, width: u32, height: u32) {
         let capacity = width * height * 4;
 
+        // Clip rectangles are clamped to the old size
+        self.clip_stack.clear();
+
         self.pixels.resize(capacity as usize, 0);
         self.width = width;
         self.height = height;
 
         let color = self.last_clear_color;
-        self.clear_screen(&color);
+        self.fill(&color);
     }
 
     /// Clone pixel buffer
@@ -44,9 +56,31 @@ impl Canvas {
         self.pixels.clone()
     }
 
+    /// Canvas width
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Canvas height
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Pixel buffer, chunks of 4 represent RGBA
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Mutable pixel buffer, chunks of 4 represent RGBA
+    ///
+    /// Writes through it ignore the clip rectangle and mask
+    pub fn pixels_mut(&mut self) -> &mut [u8] {
+        &mut self.pixels
+    }
+
     /// Get pixel data for a coordianate
     /// Panics if trying to access outside canvas
-    pub(crate) fn pixel_rgb(&self, x: u32, y: u32) -> [u8; 3] {
+    pub fn pixel_rgb(&self, x: u32, y: u32) -> [u8; 3] {
         assert_pixel(x, y, self.width, self.height);
 
         let index = (y * 4 * self.width + x * 4) as usize;
@@ -59,7 +93,7 @@ impl Canvas {
 
     /// Get pixel data for a coordianate
     /// Panics if trying to access outside canvas
-    pub(crate) fn pixel_rgb_f32(&self, x: u32, y: u32) -> [f32; 3] {
+    pub fn pixel_rgb_f32(&self, x: u32, y: u32) -> [f32; 3] {
         assert_pixel(x, y, self.width, self.height);
 
         let index = (y * 4 * self.width + x * 4) as usize;
@@ -72,7 +106,7 @@ impl Canvas {
 
     /// Get pixel data for a coordianate
     /// Panics if trying to access outside canvas
-    pub(crate) fn pixel_rgba(&self, x: u32, y: u32) -> [u8; 4] {
+    pub fn pixel_rgba(&self, x: u32, y: u32) -> [u8; 4] {
         assert_pixel(x, y, self.width, self.height);
 
         let index = (y * 4 * self.width + x * 4) as usize;
@@ -86,7 +120,7 @@ impl Canvas {
 
     /// Get pixel data for a coordianate
     /// Panics if trying to access outside canvas
-    pub(crate) fn pixel_rgba_f32(&self, x: u32, y: u32) -> [f32; 4] {
+    pub fn pixel_rgba_f32(&self, x: u32, y: u32) -> [f32; 4] {
         assert_pixel(x, y, self.width, self.height);
 
         let index = (y * 4 * self.width + x * 4) as usize;
@@ -99,9 +133,12 @@ impl Canvas {
     }
 
     /// Write pixel data to a coordinate (r,g,b,a)
-    /// Overwrites previous pixel
-    pub(crate) fn write_pixel(&mut self, x: u32, y: u32, color: &[u8; 3]) {
+    /// Overwrites previous pixel, unless clipped or masked
+    pub fn write_pixel(&mut self, x: u32, y: u32, color: &[u8; 3]) {
         assert_pixel(x, y, self.width, self.height);
+        if !self.writable(x, y) {
+            return;
+        }
 
         let index = (y * 4 * self.width + x * 4) as usize;
         self.pixels[index] = color[0];
@@ -112,8 +149,8 @@ impl Canvas {
 
     /// Write pixel data to a coordinate (r,g,b,a)
     /// Panics if trying to write outside canvas
-    /// RGBA must be in range [0,1]
-    pub(crate) fn write_pixel_f32(&mut self, x: u32, y: u32, color: &[f32; 3]) {
+    /// RGBA must be in range \[0,1\]
+    pub fn write_pixel_f32(&mut self, x: u32, y: u32, color: &[f32; 3]) {
         assert_pixel(x, y, self.width, self.height);
         assert_rgb(color);
 
@@ -128,45 +165,21 @@ impl Canvas {
 
     /// Write pixel data to a coordinate (r,g,b,a)
     /// Non premultiplied alpha blending
-    pub(crate) fn write_pixel_blend(&mut self, x: u32, y: u32, color: &[u8; 4]) {
+    pub fn write_pixel_blend(&mut self, x: u32, y: u32, color: &[u8; 4]) {
         assert_pixel(x, y, self.width, self.height);
+        if !self.writable(x, y) {
+            return;
+        }
 
-        // Alpha blending where a is over b
-        // https://en.wikipedia.org/wiki/Alpha_compositing
         let prev_color = self.pixel_rgba(x, y);
-        let a = &[
-            color[0] as f32 / 255.0,
-            color[1] as f32 / 255.0,
-            color[2] as f32 / 255.0,
-            color[3] as f32 / 255.0,
-        ];
-        let b = &[
-            prev_color[0] as f32 / 255.0,
-            prev_color[1] as f32 / 255.0,
-            prev_color[2] as f32 / 255.0,
-            prev_color[3] as f32 / 255.0,
-        ];
-
-        let alpha_a = a[3];
-        let alpha_b = b[3];
-        let alpha_over = alpha_a + alpha_b * (1.0 - alpha_a);
-
-        let result_r = (a[0] * alpha_a + b[0] * alpha_b * (1.0 - alpha_a)) / alpha_over;
-        let result_g = (a[1] * alpha_a + b[1] * alpha_b * (1.0 - alpha_a)) / alpha_over;
-        let result_b = (a[2] * alpha_a + b[2] * alpha_b * (1.0 - alpha_a)) / alpha_over;
-        let result_a = alpha_over;
-
         let index = (y * 4 * self.width + x * 4) as usize;
-        self.pixels[index] = (result_r * 255.0) as u8;
-        self.pixels[index + 1] = (result_g * 255.0) as u8;
-        self.pixels[index + 2] = (result_b * 255.0) as u8;
-        self.pixels[index + 3] = (result_a * 255.0) as u8;
+        self.pixels[index..index + 4].copy_from_slice(&alpha_over(color, &prev_color, 1.0));
     }
 
     /// Write pixel data to a coordinate (r,g,b,a)
     /// Non premultiplied alpha blending
-    /// RGBA must be in range [0,1]
-    pub(crate) fn write_pixel_blend_f32(&mut self, x: u32, y: u32, color: &[f32; 4]) {
+    /// RGBA must be in range \[0,1\]
+    pub fn write_pixel_blend_f32(&mut self, x: u32, y: u32, color: &[f32; 4]) {
         assert_pixel(x, y, self.width, self.height);
         assert_rgba(color);
 
@@ -180,19 +193,96 @@ impl Canvas {
         self.write_pixel_blend(x, y, &color);
     }
 
+    /// Draw source with its top left corner at (x, y)
+    ///
+    /// Pixels outside this canvas, clipped or masked are skipped
+    pub fn blit(&mut self, source: &Canvas, x: i32, y: i32, blend: BlendMode) {
+        self.blit_with_opacity(source, x, y, blend, 1.0);
+    }
+
+    /// Draw source with its top left corner at (x, y), its alpha scaled by opacity \[0,1\]
+    ///
+    /// Pixels outside this canvas, clipped or masked are skipped
+    pub fn blit_with_opacity(
+        &mut self,
+        source: &Canvas,
+        x: i32,
+        y: i32,
+        blend: BlendMode,
+        opacity: f32,
+    ) {
+        // Transparent pixels leave the pixel below unchanged, except when replacing
+        let skip_transparent = blend != BlendMode::Replace;
+        if skip_transparent && opacity <= 0.0 {
+            return;
+        }
+
+        // Overlapping area in source coordinates, limited to the clip rectangle
+        let (clip_x0, clip_y0, clip_x1, clip_y1) =
+            self.clip_stack
+                .last()
+                .copied()
+                .unwrap_or((0, 0, self.width, self.height));
+        let (x, y) = (x as i64, y as i64);
+        let start_x = (clip_x0 as i64 - x).max(0) as u32;
+        let start_y = (clip_y0 as i64 - y).max(0) as u32;
+        let end_x = (clip_x1 as i64 - x).clamp(0, source.width as i64) as u32;
+        let end_y = (clip_y1 as i64 - y).clamp(0, source.height as i64) as u32;
+
+        for source_y in start_y..end_y {
+            let target_y = (y + source_y as i64) as u32;
+            for source_x in start_x..end_x {
+                let source_index = ((source_y * source.width + source_x) * 4) as usize;
+                let color: [u8; 4] = source.pixels[source_index..source_index + 4]
+                    .try_into()
+                    .unwrap();
+                if skip_transparent && color[3] == 0 {
+                    continue;
+                }
+                let target_x = (x + source_x as i64) as u32;
+                if self
+                    .mask
+                    .as_ref()
+                    .is_some_and(|mask| !mask.get(target_x, target_y))
+                {
+                    continue;
+                }
+                let index = ((target_y * self.width + target_x) * 4) as usize;
+                let below: [u8; 4] = self.pixels[index..index + 4].try_into().unwrap();
+                let color = blend.apply(&color, &below, opacity);
+                self.pixels[index..index + 4].copy_from_slice(&color);
+            }
+        }
+    }
+
     /// Clears all pixels in canvas to clear color
-    pub(crate) fn clear_screen(&mut self, color: &[u8; 3]) {
+    ///
+    /// Only clears inside the clip rectangle and mask
+    pub fn clear_screen(&mut self, color: &[u8; 3]) {
+        self.last_clear_color = *color;
+        if self.clip_stack.is_empty() && self.mask.is_none() {
+            self.fill(color);
+            return;
+        }
+        for y in 0..self.height {
+            for x in 0..self.width {
+                self.write_pixel(x, y, color);
+            }
+        }
+    }
+
+    /// Sets all pixels ignoring the clip rectangle and mask
+    fn fill(&mut self, color: &[u8; 3]) {
         for pixel in self.pixels.chunks_mut(4) {
             pixel[0] = color[0];
             pixel[1] = color[1];
             pixel[2] = color[2];
             pixel[3] = 255;
         }
-        self.last_clear_color = *color;
     }
 
     /// Clears all pixels in canvas to clear color
-    pub(crate) fn clear_screen_f32(&mut self, color: &[f32; 3]) {
+    pub fn clear_screen_f32(&mut self, color: &[f32; 3]) {
         let color = &[
             (color[0] * 255.0) as u8,
             (color[1] * 255.0) as u8,
@@ -202,6 +292,204 @@ impl Canvas {
     }
 }
 
+// Clipping and masking
+impl Canvas {
+    /// Restricts writes to a rectangle within the current clip rectangle
+    ///
+    /// Undo with [`Canvas::pop_clip`]
+    pub fn push_clip(&mut self, x: u32, y: u32, width: u32, height: u32) {
+        let (x0, y0, x1, y1) =
+            self.clip_stack
+                .last()
+                .copied()
+                .unwrap_or((0, 0, self.width, self.height));
+        let clip_x0 = x.clamp(x0, x1);
+        let clip_y0 = y.clamp(y0, y1);
+        let clip_x1 = x.saturating_add(width).clamp(clip_x0, x1);
+        let clip_y1 = y.saturating_add(height).clamp(clip_y0, y1);
+        self.clip_stack.push((clip_x0, clip_y0, clip_x1, clip_y1));
+    }
+
+    /// Removes the last pushed clip rectangle
+    pub fn pop_clip(&mut self) {
+        self.clip_stack.pop();
+    }
+
+    /// Removes all clip rectangles
+    pub fn reset_clip(&mut self) {
+        self.clip_stack.clear();
+    }
+
+    /// Current clip rectangle as (x, y, width, height), None if unclipped
+    pub fn clip(&self) -> Option<(u32, u32, u32, u32)> {
+        self.clip_stack
+            .last()
+            .map(|&(x0, y0, x1, y1)| (x0, y0, x1 - x0, y1 - y0))
+    }
+
+    /// Only pixels set in mask may be written, None allows all
+    pub fn set_mask(&mut self, mask: Option<Mask>) {
+        self.mask = mask;
+    }
+
+    /// Current mask
+    pub fn mask(&self) -> Option<&Mask> {
+        self.mask.as_ref()
+    }
+
+    /// If a pixel inside the canvas may be written
+    fn writable(&self, x: u32, y: u32) -> bool {
+        let clipped = self
+            .clip_stack
+            .last()
+            .is_some_and(|&(x0, y0, x1, y1)| x < x0 || y < y0 || x >= x1 || y >= y1);
+        let masked = self.mask.as_ref().is_some_and(|mask| !mask.get(x, y));
+        !clipped && !masked
+    }
+}
+
+/// One bit per pixel deciding which pixels of a canvas may be written
+///
+/// Pixels outside the mask are never written
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mask {
+    width: u32,
+    height: u32,
+    bits: Vec<u64>,
+}
+
+impl Mask {
+    /// Mask with no pixels set
+    pub fn new(width: u32, height: u32) -> Self {
+        let len = (width as usize * height as usize).div_ceil(64);
+        Self {
+            width,
+            height,
+            bits: vec![0; len],
+        }
+    }
+
+    /// Mask set where the canvas is not fully transparent
+    ///
+    /// Draw a shape on a new canvas to mask everything else
+    pub fn from_canvas(canvas: &Canvas) -> Self {
+        let mut mask = Self::new(canvas.width, canvas.height);
+        for (i, pixel) in canvas.pixels.chunks_exact(4).enumerate() {
+            if pixel[3] != 0 {
+                mask.bits[i / 64] |= 1 << (i % 64);
+            }
+        }
+        mask
+    }
+
+    /// Mask width
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Mask height
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// If pixel (x, y) is set, false outside the mask
+    pub fn get(&self, x: u32, y: u32) -> bool {
+        if x >= self.width || y >= self.height {
+            return false;
+        }
+        let i = (y * self.width + x) as usize;
+        self.bits[i / 64] & (1 << (i % 64)) != 0
+    }
+
+    /// Sets pixel (x, y), ignored outside the mask
+    pub fn set(&mut self, x: u32, y: u32, value: bool) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        let i = (y * self.width + x) as usize;
+        if value {
+            self.bits[i / 64] |= 1 << (i % 64);
+        } else {
+            self.bits[i / 64] &= !(1 << (i % 64));
+        }
+    }
+}
+
+/// How a source pixel is combined with the pixel below it
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum BlendMode {
+    /// Source replaces the pixel below, including its alpha
+    Replace,
+    /// Non premultiplied alpha blending of source over the pixel below
+    #[default]
+    Alpha,
+    /// Source color weighted by its alpha is added to the pixel below
+    Add,
+    /// Pixel below is multiplied by the source color weighted by its alpha
+    Multiply,
+}
+
+impl BlendMode {
+    /// Combine source over below with the source alpha scaled by opacity
+    fn apply(self, source: &[u8; 4], below: &[u8; 4], opacity: f32) -> [u8; 4] {
+        let alpha = source[3] as f32 / 255.0 * opacity.clamp(0.0, 1.0);
+        let channel = |i: usize, f: &dyn Fn(f32, f32) -> f32| {
+            let result = f(source[i] as f32 / 255.0, below[i] as f32 / 255.0);
+            (result.clamp(0.0, 1.0) * 255.0) as u8
+        };
+        match self {
+            BlendMode::Replace => [source[0], source[1], source[2], (alpha * 255.0) as u8],
+            BlendMode::Alpha => alpha_over(source, below, opacity.clamp(0.0, 1.0)),
+            BlendMode::Add => [
+                channel(0, &|a, b| b + a * alpha),
+                channel(1, &|a, b| b + a * alpha),
+                channel(2, &|a, b| b + a * alpha),
+                below[3],
+            ],
+            BlendMode::Multiply => [
+                channel(0, &|a, b| b * (1.0 - alpha + a * alpha)),
+                channel(1, &|a, b| b * (1.0 - alpha + a * alpha)),
+                channel(2, &|a, b| b * (1.0 - alpha + a * alpha)),
+                below[3],
+            ],
+        }
+    }
+}
+
+/// Non premultiplied alpha blending where color a with its alpha scaled by opacity is over b
+///
+/// https://en.wikipedia.org/wiki/Alpha_compositing
+fn alpha_over(color_a: &[u8; 4], color_b: &[u8; 4], opacity: f32) -> [u8; 4] {
+    let a = &[
+        color_a[0] as f32 / 255.0,
+        color_a[1] as f32 / 255.0,
+        color_a[2] as f32 / 255.0,
+        color_a[3] as f32 / 255.0 * opacity,
+    ];
+    let b = &[
+        color_b[0] as f32 / 255.0,
+        color_b[1] as f32 / 255.0,
+        color_b[2] as f32 / 255.0,
+        color_b[3] as f32 / 255.0,
+    ];
+
+    let alpha_a = a[3];
+    let alpha_b = b[3];
+    let alpha_over = alpha_a + alpha_b * (1.0 - alpha_a);
+
+    let result_r = (a[0] * alpha_a + b[0] * alpha_b * (1.0 - alpha_a)) / alpha_over;
+    let result_g = (a[1] * alpha_a + b[1] * alpha_b * (1.0 - alpha_a)) / alpha_over;
+    let result_b = (a[2] * alpha_a + b[2] * alpha_b * (1.0 - alpha_a)) / alpha_over;
+    let result_a = alpha_over;
+
+    [
+        (result_r * 255.0) as u8,
+        (result_g * 255.0) as u8,
+        (result_b * 255.0) as u8,
+        (result_a * 255.0) as u8,
+    ]
+}
+
 /// Asserts a pixel is inside the screen
 fn assert_pixel(x: u32, y: u32, width: u32, height: u32) {
     debug_assert!(
@@ -247,7 +535,8 @@ fn assert_rgba(color: &[f32; 4]) {
 
 /// Mutable reference to pixel buffer
 ///
-/// Stored as list of u8, chunks of 4 represent RGBA
+/// Stored as list of u8, chunks of 4 represent RGBA.
+/// Writes through it ignore the clip rectangle and mask
 pub fn pixels_ref(ctx: &mut Context) -> &mut Vec<u8> {
     &mut ctx.render.canvas.pixels
 }
@@ -333,11 +622,11 @@ pub fn pixel_rgba_f32(ctx: &Context, x: u32, y: u32) -> [f32; 4] {
 
 /// Resizes the canvas
 ///
-/// Clears screen to clear color
+/// Clears screen to clear color and removes all clip rectangles
 pub fn resize(ctx: &mut Context, width: u32, height: u32) {
     ctx.render.canvas.resize(width, height);
-    ctx.render.screenshot_uploader.resize(width, height);
     ctx.render.resize_canvas_texture(width, height);
+    ctx.config.canvas_size = (width, height);
 }
 
 /// Clears all pixels to clear color
@@ -352,6 +641,43 @@ pub fn clear_screen_f32(ctx: &mut Context, color: &[f32; 3]) {
     ctx.render.canvas.clear_screen_f32(color);
 }
 
+/// Draw an offscreen canvas onto the canvas with its top left corner at (x, y)
+///
+/// Pixels outside the canvas are skipped
+pub fn draw_canvas(ctx: &mut Context, source: &Canvas, x: i32, y: i32, blend: BlendMode) {
+    ctx.render.canvas.blit(source, x, y, blend);
+}
+
+/// The canvas presented each frame
+pub fn canvas_ref(ctx: &Context) -> &Canvas {
+    &ctx.render.canvas
+}
+
+/// Mutable reference to the canvas presented each frame
+///
+/// Resizing it takes effect when the frame is presented,
+/// [`resize`] also updates the configuration
+pub fn canvas_mut(ctx: &mut Context) -> &mut Canvas {
+    &mut ctx.render.canvas
+}
+
+/// Restricts drawing on the canvas to a rectangle within the current clip rectangle
+///
+/// Respected by all writes, clears and blits. Undo with [`pop_clip`]
+pub fn push_clip(ctx: &mut Context, x: u32, y: u32, width: u32, height: u32) {
+    ctx.render.canvas.push_clip(x, y, width, height);
+}
+
+/// Removes the last pushed clip rectangle
+pub fn pop_clip(ctx: &mut Context) {
+    ctx.render.canvas.pop_clip();
+}
+
+/// Only pixels set in mask may be drawn on the canvas, None allows all
+pub fn set_mask(ctx: &mut Context, mask: Option<Mask>) {
+    ctx.render.canvas.set_mask(mask);
+}
+
 /// Canvas width
 pub fn width(ctx: &Context) -> u32 {
     ctx.render.canvas.width
@@ -367,8 +693,10 @@ pub fn height(ctx: &Context) -> u32 {
 ///
 
 #[cfg(test)]
+#[allow(clippy::empty_line_after_doc_comments)]
 mod tests {
-    use super::Canvas;
+    use super::{canvas_mut, BlendMode, Canvas, Mask};
+    use crate::Context;
 
     #[test]
     #[should_panic]
@@ -412,4 +740,130 @@ mod tests {
         canvas.write_pixel(500, 230, &[255, 255, 255]);
         canvas.pixel_rgb(500, 230);
     }
+
+    #[test]
+    fn test_resize_resets_clip() {
+        let mut canvas = Canvas::new(4, 4);
+        canvas.push_clip(0, 0, 4, 4);
+
+        canvas.resize(8, 8);
+        assert_eq!(canvas.clip(), None);
+        canvas.write_pixel(6, 6, &[255, 255, 255]);
+        assert_eq!(canvas.pixel_rgb(6, 6), [255, 255, 255]);
+    }
+
+    #[test]
+    fn test_blit_offset_clipped() {
+        let mut source = Canvas::new(4, 4);
+        source.clear_screen(&[255, 0, 0]);
+        let mut canvas = Canvas::new(8, 8);
+        canvas.blit(&source, -2, 6, BlendMode::Alpha);
+
+        assert_eq!(canvas.pixel_rgb(1, 7), [255, 0, 0]);
+        assert_eq!(canvas.pixel_rgb(2, 7), [0, 0, 0]);
+        assert_eq!(canvas.pixel_rgb(1, 5), [0, 0, 0]);
+
+        // Entirely outside
+        canvas.blit(&source, 8, 0, BlendMode::Alpha);
+        canvas.blit(&source, 0, -4, BlendMode::Alpha);
+    }
+
+    #[test]
+    fn test_blit_transparent_and_clipped() {
+        let mut source = Canvas::new(2, 1);
+        source.write_pixel(1, 0, &[255, 0, 0]);
+        let mut canvas = Canvas::new(2, 1);
+        canvas.clear_screen(&[100, 100, 100]);
+
+        canvas.push_clip(0, 0, 1, 1);
+        canvas.blit(&source, 0, 0, BlendMode::Alpha);
+        assert_eq!(canvas.pixel_rgba(1, 0), [100, 100, 100, 255]);
+
+        canvas.pop_clip();
+        canvas.blit(&source, 0, 0, BlendMode::Alpha);
+        assert_eq!(canvas.pixel_rgba(0, 0), [100, 100, 100, 255]);
+        assert_eq!(canvas.pixel_rgba(1, 0), [255, 0, 0, 255]);
+
+        // Replacing also copies transparent pixels
+        canvas.blit(&source, 0, 0, BlendMode::Replace);
+        assert_eq!(canvas.pixel_rgba(0, 0), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_blend_modes() {
+        let mut source = Canvas::new(1, 1);
+        source.write_pixel(0, 0, &[200, 100, 0]);
+        let mut canvas = Canvas::new(1, 1);
+
+        canvas.clear_screen(&[100, 100, 100]);
+        canvas.blit(&source, 0, 0, BlendMode::Add);
+        assert_eq!(canvas.pixel_rgb(0, 0), [255, 200, 100]);
+
+        canvas.clear_screen(&[255, 255, 255]);
+        canvas.blit_with_opacity(&source, 0, 0, BlendMode::Multiply, 0.0);
+        assert_eq!(canvas.pixel_rgb(0, 0), [255, 255, 255]);
+        canvas.blit(&source, 0, 0, BlendMode::Multiply);
+        assert_eq!(canvas.pixel_rgb(0, 0), [200, 100, 0]);
+
+        canvas.blit_with_opacity(&source, 0, 0, BlendMode::Replace, 0.5);
+        assert_eq!(canvas.pixel_rgba(0, 0), [200, 100, 0, 127]);
+    }
+
+    #[test]
+    fn test_clip_stack() {
+        let mut canvas = Canvas::new(8, 8);
+        canvas.push_clip(2, 2, 4, 4);
+        canvas.push_clip(4, 0, 8, 8);
+        assert_eq!(canvas.clip(), Some((4, 2, 2, 4)));
+
+        canvas.clear_screen(&[255, 255, 255]);
+        canvas.write_pixel(3, 3, &[255, 0, 0]);
+        assert_eq!(canvas.pixel_rgb(4, 2), [255, 255, 255]);
+        assert_eq!(canvas.pixel_rgb(3, 3), [0, 0, 0]);
+        assert_eq!(canvas.pixel_rgb(6, 2), [0, 0, 0]);
+
+        canvas.pop_clip();
+        let mut green = Canvas::new(8, 8);
+        green.clear_screen(&[0, 255, 0]);
+        canvas.blit(&green, 0, 0, BlendMode::Replace);
+        assert_eq!(canvas.pixel_rgb(2, 2), [0, 255, 0]);
+        assert_eq!(canvas.pixel_rgb(1, 1), [0, 0, 0]);
+
+        canvas.pop_clip();
+        assert_eq!(canvas.clip(), None);
+        canvas.write_pixel(0, 0, &[255, 0, 0]);
+        assert_eq!(canvas.pixel_rgb(0, 0), [255, 0, 0]);
+    }
+
+    #[test]
+    fn test_mask() {
+        let mut shape = Canvas::new(2, 2);
+        shape.write_pixel(1, 0, &[255, 255, 255]);
+        let mask = Mask::from_canvas(&shape);
+        assert!(mask.get(1, 0));
+        assert!(!mask.get(0, 0));
+        assert!(!mask.get(5, 5));
+
+        let mut canvas = Canvas::new(4, 4);
+        canvas.set_mask(Some(mask));
+        canvas.clear_screen(&[255, 0, 0]);
+        canvas.write_pixel_blend(3, 3, &[255, 0, 0, 255]);
+        assert_eq!(canvas.pixel_rgb(1, 0), [255, 0, 0]);
+        assert_eq!(canvas.pixel_rgb(0, 0), [0, 0, 0]);
+        assert_eq!(canvas.pixel_rgb(3, 3), [0, 0, 0]);
+
+        // Resizing clears everything
+        canvas.resize(2, 2);
+        assert_eq!(canvas.pixel_rgb(0, 0), [255, 0, 0]);
+    }
+
+    #[test]
+    fn test_canvas_mut_resize_followed_on_present() {
+        let mut ctx = Context::headless(2, 2);
+        canvas_mut(&mut ctx).resize(4, 3);
+        ctx.render.sync_canvas_size();
+
+        let screenshot = crate::debug::screenshot_canvas(&ctx);
+        assert_eq!((screenshot.width(), screenshot.height()), (4, 3));
+    }
 }
@@ -0,0 +1,252 @@
+// Buffer of recent action input and combos matched against it
+
+use crate::{action, time, Context};
+use std::collections::{btree_set::Intersection, BTreeSet, VecDeque};
+
+/// Changes kept in the input buffer
+const BUFFER_SIZE: usize = 128;
+
+/// Held actions on a frame where they changed or an action was pressed
+#[derive(Debug, Clone, PartialEq)]
+pub struct BufferedInput {
+    pub frame: usize,
+    /// Time since the app started
+    pub time: f32,
+    /// Actions pressed this frame
+    pub pressed: BTreeSet<String>,
+    /// All actions held this frame
+    pub held: BTreeSet<String>,
+}
+
+#[derive(Default)]
+pub(crate) struct ComboContext {
+    buffer: VecDeque<BufferedInput>,
+    // kept when the buffer is cleared so held actions are not buffered again
+    held: BTreeSet<String>,
+}
+
+/// Sequence of held actions entered within a number of frames,
+/// such as a quarter circle forward punch
+///
+/// Each step is the exact set of the combo's actions held, so with only down
+/// and forward held, `["forward"]` does not match while down-forward does.
+/// Other steps may be entered in between
+///
+/// ```
+/// # use pixelated::combo::Combo;
+/// let fireball = Combo::new(15)
+///     .then(&["down"])
+///     .then(&["down", "forward"])
+///     .then(&["forward"])
+///     .then(&["forward", "punch"]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Combo {
+    steps: Vec<BTreeSet<String>>,
+    actions: BTreeSet<String>,
+    frames: usize,
+}
+
+impl Combo {
+    /// Combo whose steps must all be entered within frames
+    pub fn new(frames: usize) -> Self {
+        Self {
+            steps: Vec::new(),
+            actions: BTreeSet::new(),
+            frames,
+        }
+    }
+
+    /// Adds a step where exactly these actions are held
+    pub fn then(mut self, actions: &[&str]) -> Self {
+        let step = actions
+            .iter()
+            .map(|action| action.to_string())
+            .collect::<BTreeSet<_>>();
+        self.actions.extend(step.iter().cloned());
+        self.steps.push(step);
+        self
+    }
+
+    /// The combo's actions held in input
+    fn held<'a>(&'a self, input: &'a BufferedInput) -> Intersection<'a, String> {
+        input.held.intersection(&self.actions)
+    }
+
+    /// If the last step was entered on frame, preceded by the other steps in order
+    fn matches(&self, buffer: &VecDeque<BufferedInput>, frame: usize) -> bool {
+        // Frames where the combo's held actions changed, newest first
+        let mut changes = buffer
+            .iter()
+            .enumerate()
+            .filter(|&(i, input)| i == 0 || !self.held(&buffer[i - 1]).eq(self.held(input)))
+            .map(|(_, input)| input)
+            .rev()
+            .take_while(|input| input.frame + self.frames >= frame);
+
+        match changes.next() {
+            Some(input) if input.frame == frame => {
+                let mut steps = self.steps.iter().rev();
+                if !steps.next().is_some_and(|step| self.held(input).eq(step)) {
+                    return false;
+                }
+                steps.all(|step| changes.any(|input| self.held(input).eq(step)))
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Record the actions held this frame if they changed or an action was pressed
+///
+/// Called once per frame before the user callbacks
+pub(crate) fn update_buffer(ctx: &mut Context) {
+    let actions = ctx.config.bindings.actions.keys();
+    let held = actions
+        .clone()
+        .filter(|action| action::action_pressed(ctx, action))
+        .cloned()
+        .collect::<BTreeSet<_>>();
+    let pressed = actions
+        .filter(|action| action::action_just_pressed(ctx, action))
+        .cloned()
+        .collect::<BTreeSet<_>>();
+
+    if held == ctx.combo.held && pressed.is_empty() {
+        return;
+    }
+    ctx.combo.held.clone_from(&held);
+
+    let input = BufferedInput {
+        frame: time::frame_count(ctx),
+        time: time::time_since_start(ctx),
+        pressed,
+        held,
+    };
+    let buffer = &mut ctx.combo.buffer;
+    if buffer.len() == BUFFER_SIZE {
+        buffer.pop_front();
+    }
+    buffer.push_back(input);
+}
+
+//
+// Commands
+//
+
+/// If the combo's last step was entered this frame
+///
+/// Checked per frame, so in fixed_update it is true in every fixed update
+/// of that frame, and not seen if the frame had none
+pub fn combo_matched(ctx: &Context, combo: &Combo) -> bool {
+    !combo.steps.is_empty() && combo.matches(&ctx.combo.buffer, time::frame_count(ctx))
+}
+
+/// Recent frames where held actions changed or an action was pressed, oldest first
+pub fn input_buffer(ctx: &Context) -> impl Iterator<Item = &BufferedInput> {
+    ctx.combo.buffer.iter()
+}
+
+/// Forget buffered input, e.g. after a combo so its steps are not reused
+pub fn clear_input_buffer(ctx: &mut Context) {
+    ctx.combo.buffer.clear();
+}
+
+//
+// Tests
+//
+
+#[cfg(test)]
+mod tests {
+    use super::{combo_matched, input_buffer, update_buffer, Combo};
+    use crate::action::{bind, Binding};
+    use crate::input::KeyCode;
+    use crate::Context;
+    use std::collections::BTreeSet;
+
+    /// Run a frame holding keys and return if the combo matched
+    fn frame(ctx: &mut Context, held: &[KeyCode], combo: &Combo) -> bool {
+        for keycode in [KeyCode::KeyS, KeyCode::KeyD, KeyCode::KeyJ] {
+            if held.contains(&keycode) {
+                ctx.input.keyboard.set_key(keycode);
+            } else {
+                ctx.input.keyboard.release_key(keycode);
+            }
+        }
+        ctx.time.update_time();
+        update_buffer(ctx);
+        let matched = combo_matched(ctx, combo);
+        ctx.input.keyboard.save_keys();
+        matched
+    }
+
+    fn fireball() -> Combo {
+        Combo::new(10)
+            .then(&["down"])
+            .then(&["down", "forward"])
+            .then(&["forward"])
+            .then(&["forward", "punch"])
+    }
+
+    #[test]
+    fn test_combo_matched() {
+        let mut ctx = Context::headless(8, 8);
+        bind(&mut ctx, "down", Binding::Key(KeyCode::KeyS));
+        bind(&mut ctx, "forward", Binding::Key(KeyCode::KeyD));
+        bind(&mut ctx, "punch", Binding::Key(KeyCode::KeyJ));
+        let combo = fireball();
+        let (down, forward, punch) = (KeyCode::KeyS, KeyCode::KeyD, KeyCode::KeyJ);
+
+        assert!(!frame(&mut ctx, &[down], &combo));
+        assert!(!frame(&mut ctx, &[down], &combo));
+        assert!(!frame(&mut ctx, &[down, forward], &combo));
+        assert!(!frame(&mut ctx, &[forward], &combo));
+        assert!(frame(&mut ctx, &[forward, punch], &combo));
+
+        // Only on the frame the last step is entered
+        assert!(!frame(&mut ctx, &[forward, punch], &combo));
+        assert_eq!(input_buffer(&ctx).count(), 4);
+    }
+
+    #[test]
+    fn test_combo_too_slow_or_skipped() {
+        let mut ctx = Context::headless(8, 8);
+        bind(&mut ctx, "down", Binding::Key(KeyCode::KeyS));
+        bind(&mut ctx, "forward", Binding::Key(KeyCode::KeyD));
+        bind(&mut ctx, "punch", Binding::Key(KeyCode::KeyJ));
+        let combo = fireball();
+        let (down, forward, punch) = (KeyCode::KeyS, KeyCode::KeyD, KeyCode::KeyJ);
+
+        // Skipping down-forward
+        frame(&mut ctx, &[down], &combo);
+        frame(&mut ctx, &[], &combo);
+        frame(&mut ctx, &[forward], &combo);
+        assert!(!frame(&mut ctx, &[forward, punch], &combo));
+
+        // Taking longer than the combo's frames
+        frame(&mut ctx, &[down], &combo);
+        frame(&mut ctx, &[down, forward], &combo);
+        for _ in 0..10 {
+            frame(&mut ctx, &[forward], &combo);
+        }
+        assert!(!frame(&mut ctx, &[forward, punch], &combo));
+    }
+
+    #[test]
+    fn test_press_buffered_with_same_held_actions() {
+        let mut ctx = Context::headless(8, 8);
+        bind(&mut ctx, "punch", Binding::Key(KeyCode::KeyJ));
+        let combo = fireball();
+        // Held actions as last buffered, but punch is pressed anew
+        ctx.combo.held.insert(String::from("punch"));
+
+        frame(&mut ctx, &[KeyCode::KeyJ], &combo);
+        let punch = BTreeSet::from([String::from("punch")]);
+        assert_eq!(
+            input_buffer(&ctx)
+                .map(|input| &input.pressed)
+                .collect::<Vec<_>>(),
+            [&punch]
+        );
+    }
+}
@@ -0,0 +1,331 @@
+// Application configuration
+
+use crate::{
+    action::Bindings,
+    canvas::{DEFAULT_CANVAS_HEIGHT, DEFAULT_CANVAS_WIDTH},
+    input::KeyCode,
+    Context,
+};
+use serde::{Deserialize, Serialize};
+use std::{fmt, path::Path};
+
+/// Prefix of environment variables overriding settings
+const ENV_PREFIX: &str = "PIXELATED_";
+
+/// Settings which can be overridden from the environment and command line
+const OVERRIDABLE_SETTINGS: &[&str] = &[
+    "title",
+    "window_size",
+    "canvas_size",
+    "window_scale",
+    "resizable",
+    "vsync",
+    "icon",
+];
+
+/// Overridable settings which are true when given as a flag without a value
+const BOOL_SETTINGS: &[&str] = &["resizable", "vsync"];
+
+/// Settings applied before the first frame
+///
+/// Missing values in config files use the defaults
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppConfig {
+    /// Window title
+    pub title: String,
+    /// Initial logical window size, overrides window_scale
+    pub window_size: Option<(u32, u32)>,
+    /// Initial canvas size in pixels
+    pub canvas_size: (u32, u32),
+    /// Initial window size as a multiple of the canvas size
+    pub window_scale: Option<u32>,
+    /// If the window can be resized
+    pub resizable: bool,
+    /// If presentation waits for vsync
+    pub vsync: bool,
+    /// Path to a png or jpeg used as window icon
+    pub icon: Option<String>,
+    /// Inputs bound to named actions and axes, changed at runtime by the action module
+    pub bindings: Bindings,
+    /// Key toggling the debug overlay
+    pub debug_overlay_key: Option<KeyCode>,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            title: String::from("pixelated"),
+            window_size: None,
+            canvas_size: (DEFAULT_CANVAS_WIDTH, DEFAULT_CANVAS_HEIGHT),
+            window_scale: None,
+            resizable: true,
+            vsync: true,
+            icon: None,
+            bindings: Bindings::default(),
+            debug_overlay_key: None,
+        }
+    }
+}
+
+/// Error while loading, saving or overriding configuration
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(std::io::Error),
+    Parse(toml::de::Error),
+    Serialize(toml::ser::Error),
+    InvalidValue { setting: String, value: String },
+    UnknownSetting(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(err) => write!(f, "could not access config file: {err}"),
+            ConfigError::Parse(err) => write!(f, "could not parse config: {err}"),
+            ConfigError::Serialize(err) => write!(f, "could not serialize config: {err}"),
+            ConfigError::InvalidValue { setting, value } => {
+                write!(f, "invalid value {value:?} for setting {setting}")
+            }
+            ConfigError::UnknownSetting(setting) => write!(f, "unknown setting {setting}"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl AppConfig {
+    /// Load configuration from a TOML file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let contents = std::fs::read_to_string(path).map_err(ConfigError::Io)?;
+        Self::from_toml(&contents)
+    }
+
+    /// Parse configuration from TOML
+    pub fn from_toml(contents: &str) -> Result<Self, ConfigError> {
+        toml::from_str(contents).map_err(ConfigError::Parse)
+    }
+
+    /// Serialize configuration to TOML
+    pub fn to_toml(&self) -> Result<String, ConfigError> {
+        toml::to_string_pretty(self).map_err(ConfigError::Serialize)
+    }
+
+    /// Save configuration to a TOML file
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ConfigError> {
+        std::fs::write(path, self.to_toml()?).map_err(ConfigError::Io)
+    }
+
+    /// Override settings from environment variables
+    ///
+    /// Each setting is read from its uppercased name prefixed with `PIXELATED_`,
+    /// e.g. `PIXELATED_CANVAS_SIZE=128x128`
+    pub fn with_env_overrides(mut self) -> Result<Self, ConfigError> {
+        for setting in OVERRIDABLE_SETTINGS {
+            let name = format!("{}{}", ENV_PREFIX, setting.to_uppercase());
+            if let Ok(value) = std::env::var(name) {
+                self.set(setting, &value)?;
+            }
+        }
+        Ok(self)
+    }
+
+    /// Override settings from command line arguments
+    ///
+    /// Accepts `--setting=value` and `--setting value` with dashes or underscores,
+    /// e.g. `--window-scale 3`. A bool setting without a value is set to true,
+    /// e.g. `--vsync level1.map`. Other arguments are ignored
+    pub fn with_args<I, S>(mut self, args: I) -> Result<Self, ConfigError>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut args = args.into_iter().map(Into::into).peekable();
+        while let Some(arg) = args.next() {
+            let Some(arg) = arg.strip_prefix("--") else {
+                continue;
+            };
+            let (setting, value) = match arg.split_once('=') {
+                Some((setting, value)) => (setting.replace('-', "_"), Some(value.to_string())),
+                None => (arg.replace('-', "_"), None),
+            };
+            if !OVERRIDABLE_SETTINGS.contains(&setting.as_str()) {
+                continue;
+            }
+            // A bool flag only takes the next argument if it is a bool
+            let flag = BOOL_SETTINGS.contains(&setting.as_str())
+                && args.peek().map_or(true, |next| parse_bool(next).is_none());
+            if value.is_none() && flag {
+                self.set(&setting, "true")?;
+                continue;
+            }
+            let value = match value.or_else(|| args.next()) {
+                Some(value) => value,
+                None => {
+                    return Err(ConfigError::InvalidValue {
+                        setting,
+                        value: String::new(),
+                    })
+                }
+            };
+            self.set(&setting, &value)?;
+        }
+        Ok(self)
+    }
+
+    /// Set a setting from its string representation
+    ///
+    /// Sizes are written as `WIDTHxHEIGHT`, optional settings are cleared by `none`
+    pub fn set(&mut self, setting: &str, value: &str) -> Result<(), ConfigError> {
+        let invalid = || ConfigError::InvalidValue {
+            setting: setting.to_string(),
+            value: value.to_string(),
+        };
+        let none = value.eq_ignore_ascii_case("none");
+
+        match setting {
+            "title" => self.title = value.to_string(),
+            "window_size" if none => self.window_size = None,
+            "window_size" => self.window_size = Some(parse_size(value).ok_or_else(invalid)?),
+            "canvas_size" => self.canvas_size = parse_size(value).ok_or_else(invalid)?,
+            "window_scale" if none => self.window_scale = None,
+            "window_scale" => self.window_scale = Some(value.parse().map_err(|_| invalid())?),
+            "resizable" => self.resizable = parse_bool(value).ok_or_else(invalid)?,
+            "vsync" => self.vsync = parse_bool(value).ok_or_else(invalid)?,
+            "icon" if none => self.icon = None,
+            "icon" => self.icon = Some(value.to_string()),
+            _ => return Err(ConfigError::UnknownSetting(setting.to_string())),
+        }
+        Ok(())
+    }
+}
+
+/// Parse sizes written as `WIDTHxHEIGHT`
+fn parse_size(value: &str) -> Option<(u32, u32)> {
+    let (width, height) = value.split_once(['x', 'X'])?;
+    Some((width.trim().parse().ok()?, height.trim().parse().ok()?))
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "true" | "1" | "yes" | "on" => Some(true),
+        "false" | "0" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+//
+// Commands
+//
+
+/// Configuration the app is running with
+///
+/// Kept up to date with window resizes, canvas resizes and vsync changes
+pub fn current(ctx: &Context) -> &AppConfig {
+    &ctx.config
+}
+
+/// Mutable configuration the app is running with
+///
+/// Changes are not applied, only saved with [`save`], except for bindings which are used directly
+pub fn current_mut(ctx: &mut Context) -> &mut AppConfig {
+    &mut ctx.config
+}
+
+/// Save the configuration the app is running with to a TOML file
+///
+/// Includes the current action and axis bindings
+pub fn save(ctx: &Context, path: impl AsRef<Path>) -> Result<(), ConfigError> {
+    ctx.config.save(path)
+}
+
+//
+// Tests
+//
+
+#[cfg(test)]
+mod tests {
+    use super::{AppConfig, ConfigError};
+    use crate::{action::Binding, input::KeyCode};
+
+    #[test]
+    fn test_toml_round_trip() {
+        let mut config = AppConfig {
+            title: String::from("game"),
+            window_scale: Some(3),
+            canvas_size: (128, 64),
+            ..Default::default()
+        };
+        config.bindings.actions.insert(
+            String::from("jump"),
+            vec![Binding::Key(KeyCode::Space), Binding::Key(KeyCode::KeyW)],
+        );
+
+        let toml = config.to_toml().unwrap();
+        assert_eq!(AppConfig::from_toml(&toml).unwrap(), config);
+    }
+
+    #[test]
+    fn test_missing_values_use_defaults() {
+        let config = AppConfig::from_toml("vsync = false").unwrap();
+
+        assert!(!config.vsync);
+        assert_eq!(config.title, AppConfig::default().title);
+    }
+
+    #[test]
+    fn test_args_override() {
+        let args = [
+            "game",
+            "--canvas-size=32x16",
+            "--window_scale",
+            "4",
+            "--vsync=off",
+            "--level",
+            "2",
+        ];
+        let config = AppConfig::default().with_args(args).unwrap();
+
+        assert_eq!(config.canvas_size, (32, 16));
+        assert_eq!(config.window_scale, Some(4));
+        assert!(!config.vsync);
+    }
+
+    #[test]
+    fn test_bool_flag_without_value() {
+        let config = AppConfig {
+            vsync: false,
+            resizable: false,
+            ..Default::default()
+        };
+        let config = config
+            .with_args([
+                "--vsync",
+                "--canvas-size",
+                "32x16",
+                "--resizable",
+                "level1.map",
+            ])
+            .unwrap();
+
+        assert!(config.vsync);
+        assert!(config.resizable);
+        assert_eq!(config.canvas_size, (32, 16));
+
+        let config = config
+            .with_args(["--vsync", "false", "level1.map"])
+            .unwrap();
+        assert!(!config.vsync);
+    }
+
+    #[test]
+    fn test_invalid_override() {
+        let result = AppConfig::default().with_args(["--canvas-size", "big"]);
+
+        assert!(matches!(result, Err(ConfigError::InvalidValue { .. })));
+        assert!(matches!(
+            AppConfig::default().set("fullscreen", "true"),
+            Err(ConfigError::UnknownSetting(_))
+        ));
+    }
+}
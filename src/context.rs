@@ -1,4 +1,7 @@
-use crate::{input::InputContext, render::RenderContext, time::TimeContext};
+use crate::{
+    combo::ComboContext, config::AppConfig, debug::DebugContext, input::InputContext,
+    profile::ProfileContext, render::RenderContext, time::TimeContext,
+};
 
 /// Holds all the neccesary state for running the engine
 ///
@@ -7,4 +10,29 @@ pub struct Context {
     pub(crate) render: RenderContext,
     pub(crate) time: TimeContext,
     pub(crate) input: InputContext,
+    pub(crate) config: AppConfig,
+    pub(crate) profile: ProfileContext,
+    pub(crate) debug: DebugContext,
+    pub(crate) combo: ComboContext,
+}
+
+#[cfg(test)]
+impl Context {
+    /// Context without a window or terminal for testing commands
+    pub(crate) fn headless(width: u32, height: u32) -> Self {
+        use crate::terminal::TerminalRenderer;
+
+        Self {
+            render: RenderContext::new_terminal(TerminalRenderer::new(0, 0), width, height),
+            time: TimeContext::default(),
+            input: InputContext::default(),
+            config: AppConfig {
+                canvas_size: (width, height),
+                ..Default::default()
+            },
+            profile: ProfileContext::default(),
+            debug: DebugContext::default(),
+            combo: ComboContext::default(),
+        }
+    }
 }
@@ -0,0 +1,228 @@
+// Debug overlay drawn on top of the canvas
+
+use crate::{
+    canvas::Canvas,
+    font::{self, GLYPH_HEIGHT},
+    input::{self, KeyCode},
+    Context,
+};
+use std::borrow::Cow;
+
+/// Distance between the overlay and the canvas edge, and between its lines
+const MARGIN: i32 = 2;
+
+/// Height of the frame time graph in pixels
+const GRAPH_HEIGHT: u32 = 16;
+
+/// Frame time at the top of the graph unless a frame took longer
+const GRAPH_MIN_SCALE: f32 = 1.0 / 30.0;
+
+/// Frame time marked by a line in the graph
+const GRAPH_TARGET: f32 = 1.0 / 60.0;
+
+const BACKGROUND_COLOR: [u8; 4] = [0, 0, 0, 180];
+const TEXT_COLOR: [u8; 3] = [255, 255, 255];
+const GRAPH_COLOR: [u8; 3] = [80, 220, 120];
+const GRAPH_SLOW_COLOR: [u8; 3] = [240, 80, 80];
+const GRAPH_TARGET_COLOR: [u8; 3] = [120, 120, 120];
+
+#[derive(Default)]
+pub(crate) struct DebugContext {
+    enabled: bool,
+    in_screenshots: bool,
+}
+
+/// Toggles the overlay and draws it on the copy of the canvas which is presented instead
+///
+/// Called after the user render
+pub(crate) fn update_overlay(ctx: &mut Context) {
+    if let Some(key) = ctx.config.debug_overlay_key {
+        if input::key_just_pressed(ctx, key) {
+            ctx.debug.enabled = !ctx.debug.enabled;
+        }
+    }
+
+    if !ctx.debug.enabled {
+        return;
+    }
+
+    let lines = overlay_lines(ctx);
+    let frame_times = ctx.time.recent_frame_times().collect::<Vec<_>>();
+    draw_overlay(ctx.render.overlay_canvas(), &lines, &frame_times);
+}
+
+/// Draw the overlay as of now onto canvas
+fn draw_current_overlay(ctx: &Context, canvas: &mut Canvas) {
+    let lines = overlay_lines(ctx);
+    let frame_times = ctx.time.recent_frame_times().collect::<Vec<_>>();
+    draw_overlay(canvas, &lines, &frame_times);
+}
+
+fn overlay_lines(ctx: &Context) -> Vec<String> {
+    let canvas = &ctx.render.canvas;
+    let (window_width, window_height) = ctx.render.window_size();
+    let (mouse_x, mouse_y) = input::mouse_pos_pixel(ctx);
+    let frame_time = ctx.time.frame_stats();
+
+    let mut lines = vec![
+        format!("FPS {:.1}", 1.0 / frame_time.average.max(f32::EPSILON)),
+        format!(
+            "MS {:.2} MAX {:.2}",
+            frame_time.average * 1000.0,
+            frame_time.max * 1000.0
+        ),
+        format!("CANVAS {}X{}", canvas.width, canvas.height),
+        format!("WINDOW {}X{}", window_width, window_height),
+    ];
+    if input::mouse_on_screen(ctx) {
+        lines.push(format!("MOUSE {},{}", mouse_x, mouse_y));
+    }
+    lines
+}
+
+/// Draw text lines followed by a frame time graph in the top left corner
+fn draw_overlay(canvas: &mut Canvas, lines: &[String], frame_times: &[f32]) {
+    let line_height = GLYPH_HEIGHT as i32 + MARGIN;
+    let text_width = lines.iter().map(|line| font::text_width(line)).max();
+    let width = text_width.unwrap_or(0).max(frame_times.len() as u32) as i32 + MARGIN * 2;
+    let height = lines.len() as i32 * line_height + GRAPH_HEIGHT as i32 + MARGIN * 2;
+
+    // Background
+    for y in 0..height.min(canvas.height as i32) {
+        for x in 0..width.min(canvas.width as i32) {
+            canvas.write_pixel_blend(x as u32, y as u32, &BACKGROUND_COLOR);
+        }
+    }
+
+    for (i, line) in lines.iter().enumerate() {
+        font::draw_text(
+            canvas,
+            MARGIN,
+            MARGIN + i as i32 * line_height,
+            line,
+            &TEXT_COLOR,
+        );
+    }
+
+    // Frame time graph, one column per frame
+    let graph_bottom = height - MARGIN - 1;
+    let scale = frame_times.iter().copied().fold(GRAPH_MIN_SCALE, f32::max);
+    let bar_height = |seconds: f32| (seconds / scale * GRAPH_HEIGHT as f32).round() as i32;
+
+    for (x, &seconds) in frame_times.iter().enumerate() {
+        let color = if seconds > GRAPH_TARGET * 1.5 {
+            &GRAPH_SLOW_COLOR
+        } else {
+            &GRAPH_COLOR
+        };
+        for y in 0..bar_height(seconds) {
+            font::write_clipped(canvas, MARGIN + x as i32, graph_bottom - y, color);
+        }
+    }
+
+    let target_y = graph_bottom - bar_height(GRAPH_TARGET);
+    for x in 0..frame_times.len() as i32 {
+        font::write_clipped(canvas, MARGIN + x, target_y, &GRAPH_TARGET_COLOR);
+    }
+}
+
+//
+// Commands
+//
+
+/// Shows or hides the debug overlay
+///
+/// The overlay is drawn on top of the presented frame,
+/// leaving the pixel buffer untouched
+pub fn set_overlay_enabled(ctx: &mut Context, enabled: bool) {
+    ctx.debug.enabled = enabled;
+}
+
+/// If the debug overlay is shown
+pub fn overlay_enabled(ctx: &Context) -> bool {
+    ctx.debug.enabled
+}
+
+/// Key toggling the debug overlay, None disables toggling
+pub fn set_overlay_key(ctx: &mut Context, key: Option<KeyCode>) {
+    ctx.config.debug_overlay_key = key;
+}
+
+/// If screenshots include the debug overlay while it is shown
+pub fn set_overlay_in_screenshots(ctx: &mut Context, include: bool) {
+    ctx.debug.in_screenshots = include;
+}
+
+/// Canvas exported by screenshots: the canvas with its layers,
+/// plus the overlay if shown and requested
+///
+/// Composed when exporting, so it matches the canvas as drawn so far this frame
+pub(crate) fn screenshot_canvas(ctx: &Context) -> Cow<'_, Canvas> {
+    let frame = ctx.render.frame();
+    if !(ctx.debug.enabled && ctx.debug.in_screenshots) {
+        return frame;
+    }
+    let mut canvas = frame.into_owned();
+    canvas.reset_clip();
+    canvas.set_mask(None);
+    draw_current_overlay(ctx, &mut canvas);
+    Cow::Owned(canvas)
+}
+
+//
+// Tests
+//
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        draw_overlay, screenshot_canvas, set_overlay_enabled, set_overlay_in_screenshots,
+        update_overlay, BACKGROUND_COLOR,
+    };
+    use crate::canvas::Canvas;
+    use crate::Context;
+
+    #[test]
+    fn test_overlay_clipped_to_small_canvas() {
+        let mut canvas = Canvas::new(8, 8);
+        draw_overlay(&mut canvas, &[String::from("FPS 60")], &[0.016; 100]);
+
+        assert_eq!(canvas.pixel_rgb(2, 2), [255, 255, 255]);
+    }
+
+    #[test]
+    fn test_overlay_graph() {
+        let mut canvas = Canvas::new(128, 64);
+        canvas.clear_screen(&[255, 255, 255]);
+        draw_overlay(&mut canvas, &[], &[1.0 / 30.0, 0.0]);
+
+        // Background darkens the canvas
+        let dark = 255 - BACKGROUND_COLOR[3];
+        assert!(canvas.pixel_rgb(3, 0)[0] <= dark + 1);
+
+        // Slowest frame reaches the top of the graph, an idle frame draws nothing
+        assert_eq!(canvas.pixel_rgb(2, 2), [240, 80, 80]);
+        assert_eq!(canvas.pixel_rgb(3, 17), canvas.pixel_rgb(3, 0));
+    }
+
+    #[test]
+    fn test_screenshot_overlay_is_current() {
+        let mut ctx = Context::headless(64, 64);
+        set_overlay_enabled(&mut ctx, true);
+        set_overlay_in_screenshots(&mut ctx, true);
+
+        // Previous frame
+        update_overlay(&mut ctx);
+        ctx.render.finish_frame();
+
+        // Drawing this frame shows up in a screenshot taken during update
+        ctx.render.canvas.clear_screen(&[255, 255, 255]);
+        let screenshot = screenshot_canvas(&ctx);
+        assert_eq!(screenshot.pixel_rgb(63, 63), [255, 255, 255]);
+        assert!(screenshot.pixel_rgb(0, 0)[0] < 255);
+        assert_eq!(ctx.render.canvas.pixel_rgb(0, 0), [255, 255, 255]);
+
+        set_overlay_in_screenshots(&mut ctx, false);
+        assert_eq!(screenshot_canvas(&ctx).pixel_rgb(0, 0), [255, 255, 255]);
+    }
+}
@@ -0,0 +1,293 @@
+// Encode pixels as terminal graphics protocol escape sequences
+
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+};
+
+/// Maximum amount of colors in a sixel palette
+const SIXEL_MAX_COLORS: usize = 256;
+
+/// Maximum size of a kitty graphics payload chunk
+const KITTY_CHUNK_SIZE: usize = 4096;
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encode RGBA pixels as a Sixel image
+///
+/// Each pixel is drawn as a scale x scale square.
+/// Images with more than 256 colors are quantized to a 6x6x6 color cube
+pub(crate) fn encode_sixel<W: Write>(
+    out: &mut W,
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    scale: u32,
+) -> io::Result<()> {
+    let scale = scale.max(1);
+    let (palette, indices) = sixel_palette(pixels);
+    let (scaled_width, scaled_height) = (width * scale, height * scale);
+
+    write!(out, "\x1bPq\"1;1;{};{}", scaled_width, scaled_height)?;
+    for (i, color) in palette.iter().enumerate() {
+        write!(
+            out,
+            "#{};2;{};{};{}",
+            i,
+            color[0] as u32 * 100 / 255,
+            color[1] as u32 * 100 / 255,
+            color[2] as u32 * 100 / 255
+        )?;
+    }
+
+    let index_at = |x: u32, y: u32| indices[((y / scale) * width + x / scale) as usize];
+
+    // Sixel row of each color, only allocated once the color is used
+    let mut bands = vec![Vec::new(); palette.len()];
+    let mut used = Vec::new();
+    for band_y in (0..scaled_height).step_by(6) {
+        let rows = (scaled_height - band_y).min(6);
+
+        // Build the rows of all colors present in this band in one pass
+        for row in 0..rows {
+            for x in 0..scaled_width {
+                let color = index_at(x, band_y + row) as usize;
+                let band = &mut bands[color];
+                if band.is_empty() {
+                    band.resize(scaled_width as usize, 0);
+                    used.push(color);
+                }
+                band[x as usize] |= 1 << row;
+            }
+        }
+
+        used.sort_unstable();
+        for (i, &color) in used.iter().enumerate() {
+            if i > 0 {
+                out.write_all(b"$")?;
+            }
+            write!(out, "#{}", color)?;
+            write_sixel_runs(out, &bands[color])?;
+            bands[color].clear();
+        }
+        used.clear();
+        out.write_all(b"-")?;
+    }
+
+    out.write_all(b"\x1b\\")
+}
+
+/// Palette and palette index of each pixel
+fn sixel_palette(pixels: &[u8]) -> (Vec<[u8; 3]>, Vec<u8>) {
+    let mut palette = Vec::new();
+    let mut lookup = HashMap::new();
+    let mut indices = Vec::with_capacity(pixels.len() / 4);
+
+    for pixel in pixels.chunks(4) {
+        let color = [pixel[0], pixel[1], pixel[2]];
+        let index = *lookup.entry(color).or_insert_with(|| {
+            palette.push(color);
+            palette.len() - 1
+        });
+        if palette.len() > SIXEL_MAX_COLORS {
+            return sixel_cube_palette(pixels);
+        }
+        indices.push(index as u8);
+    }
+
+    (palette, indices)
+}
+
+/// 6x6x6 color cube palette used when an image has too many colors
+fn sixel_cube_palette(pixels: &[u8]) -> (Vec<[u8; 3]>, Vec<u8>) {
+    let level = |c: u8| (c as u32 * 5 + 127) / 255;
+
+    let mut palette = Vec::with_capacity(216);
+    for r in 0..6 {
+        for g in 0..6 {
+            for b in 0..6 {
+                palette.push([r * 51, g * 51, b * 51]);
+            }
+        }
+    }
+
+    let indices = pixels
+        .chunks(4)
+        .map(|pixel| (level(pixel[0]) * 36 + level(pixel[1]) * 6 + level(pixel[2])) as u8)
+        .collect();
+
+    (palette, indices)
+}
+
+/// Write one color row of a sixel band using run length encoding
+fn write_sixel_runs<W: Write>(out: &mut W, band: &[u8]) -> io::Result<()> {
+    let mut x = 0;
+    while x < band.len() {
+        let sixel = band[x];
+        let run = band[x..].iter().take_while(|&&s| s == sixel).count();
+        let char = 63 + sixel;
+        if run > 3 {
+            write!(out, "!{}{}", run, char as char)?;
+        } else {
+            for _ in 0..run {
+                out.write_all(&[char])?;
+            }
+        }
+        x += run;
+    }
+    Ok(())
+}
+
+/// Encode RGBA pixels using the kitty graphics protocol
+///
+/// Each pixel is drawn as a scale x scale square.
+/// An id lets a later image replace this one
+pub(crate) fn encode_kitty<W: Write>(
+    out: &mut W,
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    scale: u32,
+    id: Option<u32>,
+) -> io::Result<()> {
+    let scale = scale.max(1);
+    let (scaled_width, scaled_height) = (width * scale, height * scale);
+
+    let data = if scale == 1 {
+        base64(pixels)
+    } else {
+        let mut scaled = Vec::with_capacity((scaled_width * scaled_height * 4) as usize);
+        for y in 0..scaled_height {
+            for x in 0..scaled_width {
+                let index = (((y / scale) * width + x / scale) * 4) as usize;
+                scaled.extend_from_slice(&pixels[index..index + 4]);
+            }
+        }
+        base64(&scaled)
+    };
+
+    let chunks = data.chunks(KITTY_CHUNK_SIZE).collect::<Vec<_>>();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = (i + 1 < chunks.len()) as u8;
+        if i == 0 {
+            write!(
+                out,
+                "\x1b_Ga=T,f=32,s={},v={},q=2",
+                scaled_width, scaled_height
+            )?;
+            if let Some(id) = id {
+                write!(out, ",i={}", id)?;
+            }
+            write!(out, ",m={};", more)?;
+        } else {
+            write!(out, "\x1b_Gm={};", more)?;
+        }
+        out.write_all(chunk)?;
+        out.write_all(b"\x1b\\")?;
+    }
+
+    Ok(())
+}
+
+/// Delete a kitty image and all of its placements
+pub(crate) fn encode_kitty_delete<W: Write>(out: &mut W, id: u32) -> io::Result<()> {
+    write!(out, "\x1b_Ga=d,d=I,i={},q=2\x1b\\", id)
+}
+
+/// Standard base64 with padding
+fn base64(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        out.push(BASE64_ALPHABET[(n >> 18) as usize & 63]);
+        out.push(BASE64_ALPHABET[(n >> 12) as usize & 63]);
+        out.push(if chunk.len() > 1 {
+            BASE64_ALPHABET[(n >> 6) as usize & 63]
+        } else {
+            b'='
+        });
+        out.push(if chunk.len() > 2 {
+            BASE64_ALPHABET[n as usize & 63]
+        } else {
+            b'='
+        });
+    }
+    out
+}
+
+//
+// Tests
+//
+
+#[cfg(test)]
+mod tests {
+    use super::{base64, encode_kitty, encode_sixel, sixel_palette};
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b""), b"");
+        assert_eq!(base64(b"f"), b"Zg==");
+        assert_eq!(base64(b"fo"), b"Zm8=");
+        assert_eq!(base64(b"foo"), b"Zm9v");
+        assert_eq!(base64(b"foobar"), b"Zm9vYmFy");
+    }
+
+    #[test]
+    fn test_sixel_single_pixel() {
+        let mut out = Vec::new();
+        encode_sixel(&mut out, &[255, 0, 0, 255], 1, 1, 1).unwrap();
+
+        assert_eq!(out, b"\x1bPq\"1;1;1;1#0;2;100;0;0#0@-\x1b\\");
+    }
+
+    #[test]
+    fn test_sixel_run_length_and_scale() {
+        let mut out = Vec::new();
+        encode_sixel(&mut out, &[0, 0, 0, 255, 255, 255, 255, 255], 2, 1, 3).unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        // 6x3 pixels, each color covering 3 columns and rows
+        assert!(out.contains("\"1;1;6;3"));
+        assert!(out.contains("#0FFF???$#1???FFF-"));
+    }
+
+    #[test]
+    fn test_sixel_bands() {
+        let (r, g, b) = ([255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255]);
+        let pixels = [r, g, r, g, r, g, b].concat();
+        let mut out = Vec::new();
+        encode_sixel(&mut out, &pixels, 1, 7, 1).unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        assert!(out.ends_with("#0T$#1i-#2@-\x1b\\"));
+    }
+
+    #[test]
+    fn test_sixel_palette_quantizes_many_colors() {
+        let pixels = (0..300u32)
+            .flat_map(|i| [i as u8, (i / 256) as u8, 0, 255])
+            .collect::<Vec<_>>();
+        let (palette, indices) = sixel_palette(&pixels);
+
+        assert_eq!(palette.len(), 216);
+        assert_eq!(indices.len(), 300);
+    }
+
+    #[test]
+    fn test_kitty_chunks() {
+        let pixels = vec![255; 64 * 64 * 4];
+        let mut out = Vec::new();
+        encode_kitty(&mut out, &pixels, 64, 64, 1, Some(1)).unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        assert!(out.starts_with("\x1b_Ga=T,f=32,s=64,v=64,q=2,i=1,m=1;"));
+        assert!(out.contains("\x1b_Gm=0;"));
+        assert_eq!(out.matches("\x1b\\").count(), 6);
+    }
+}
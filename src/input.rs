@@ -1,15 +1,327 @@
 // Re-exports
-pub use winit::event::MouseButton;
-pub use winit::keyboard::KeyCode;
+pub use winit::event::{MouseButton, TouchPhase};
+pub use winit::keyboard::{Key, KeyCode, NamedKey};
 
-use crate::{render::RenderContext, Context};
-use std::collections::HashSet;
-use winit::event::Modifiers;
+use crate::{render::RenderContext, time, Context};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    time::Instant,
+};
+use winit::{event::Modifiers, keyboard::PhysicalKey};
 
 #[derive(Default)]
 pub(crate) struct InputContext {
     pub keyboard: KeyboardContext,
     pub mouse: MouseContext,
+    pub gamepad: GamepadContext,
+    pub text: TextContext,
+    pub touch: TouchContext,
+    pub events: Vec<InputEvent>,
+    // keys and buttons at the end of the last fixed update
+    fixed_previous: Option<LatchedInput>,
+}
+
+/// Input event with the time it arrived
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputEvent {
+    pub time: Instant,
+    pub kind: InputEventKind,
+}
+
+/// What happened in an input event
+///
+/// Mouse and touch positions are in physical window coordinates
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputEventKind {
+    /// A key was pressed or repeated while held
+    ///
+    /// key is None for keys without a KeyCode
+    KeyPressed {
+        key: Option<KeyCode>,
+        logical: Key,
+        repeat: bool,
+    },
+    KeyReleased {
+        key: Option<KeyCode>,
+        logical: Key,
+    },
+    /// Text was typed or committed by an input method
+    Text(String),
+    MouseMoved {
+        x: f64,
+        y: f64,
+    },
+    /// Raw mouse movement, also reported while the cursor is grabbed
+    MouseMotion {
+        dx: f64,
+        dy: f64,
+    },
+    MouseButtonPressed(MouseButton),
+    MouseButtonReleased(MouseButton),
+    MouseWheel {
+        dx: f64,
+        dy: f64,
+    },
+    MouseEntered,
+    MouseLeft,
+    /// A finger or pen touched, moved on or lifted from the screen
+    Touch {
+        id: u64,
+        phase: TouchPhase,
+        x: f64,
+        y: f64,
+        force: Option<f32>,
+    },
+    GamepadConnected {
+        slot: usize,
+    },
+    GamepadDisconnected {
+        slot: usize,
+    },
+    GamepadButtonPressed {
+        slot: usize,
+        button: GamepadButton,
+    },
+    GamepadButtonReleased {
+        slot: usize,
+        button: GamepadButton,
+    },
+    GamepadAxisChanged {
+        slot: usize,
+        axis: GamepadAxis,
+        value: f32,
+    },
+}
+
+impl InputContext {
+    /// Queue an event arriving now
+    pub(crate) fn record(&mut self, kind: InputEventKind) {
+        self.events.push(InputEvent {
+            time: Instant::now(),
+            kind,
+        });
+    }
+
+    /// Press a key and queue the event
+    pub(crate) fn press_key(&mut self, physical: PhysicalKey, logical: Key, repeat: bool) {
+        self.keyboard.press(physical, logical.clone());
+        self.record(InputEventKind::KeyPressed {
+            key: keycode(physical),
+            logical,
+            repeat,
+        });
+    }
+
+    /// Release a key and queue the event
+    pub(crate) fn release_key(&mut self, physical: PhysicalKey, logical: Key) {
+        self.keyboard.release(physical);
+        self.record(InputEventKind::KeyReleased {
+            key: keycode(physical),
+            logical,
+        });
+    }
+
+    /// Add typed text and queue the event
+    pub(crate) fn type_text(&mut self, text: &str) {
+        let text = self.text.insert(text);
+        if !text.is_empty() {
+            self.record(InputEventKind::Text(text));
+        }
+    }
+
+    /// Add the clipboard's text as typed text
+    pub(crate) fn paste(&mut self) {
+        let text = self.text.clipboard.text();
+        self.type_text(&text);
+    }
+
+    /// Current keys and buttons
+    fn current(&self) -> LatchedInput {
+        LatchedInput {
+            keys: self.keyboard.pressed.clone(),
+            logical_keys: self.keyboard.logical_pressed.clone(),
+            modifiers: self.keyboard.pressed_modifiers.clone(),
+            mouse_buttons: self.mouse.pressed.clone(),
+            gamepad_buttons: self
+                .gamepad
+                .slots
+                .iter()
+                .map(|slot| slot.as_ref().map(|state| state.pressed.clone()))
+                .collect(),
+        }
+    }
+
+    /// Replace the previous keys and buttons, returning the replaced ones
+    ///
+    /// Gamepads missing from previous keep their own previous buttons
+    fn replace_previous(&mut self, previous: LatchedInput) -> LatchedInput {
+        let keyboard = &mut self.keyboard;
+        let gamepad_buttons = previous
+            .gamepad_buttons
+            .into_iter()
+            .chain(std::iter::repeat_with(|| None));
+        LatchedInput {
+            keys: std::mem::replace(&mut keyboard.previous_pressed, previous.keys),
+            logical_keys: std::mem::replace(
+                &mut keyboard.previous_logical_pressed,
+                previous.logical_keys,
+            ),
+            modifiers: std::mem::replace(
+                &mut keyboard.previous_pressed_modifiers,
+                previous.modifiers,
+            ),
+            mouse_buttons: std::mem::replace(
+                &mut self.mouse.previous_pressed,
+                previous.mouse_buttons,
+            ),
+            gamepad_buttons: self
+                .gamepad
+                .slots
+                .iter_mut()
+                .zip(gamepad_buttons)
+                .map(|(slot, buttons)| {
+                    slot.as_mut().map(|state| match buttons {
+                        Some(buttons) => std::mem::replace(&mut state.previous_pressed, buttons),
+                        None => state.previous_pressed.clone(),
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    /// Treat the current keys and buttons as the previous ones, so just pressed
+    /// and released queries are false until unlatched
+    ///
+    /// Returns the previous state to restore with unlatch
+    pub(crate) fn latch(&mut self) -> LatchedInput {
+        let current = self.current();
+        self.replace_previous(current)
+    }
+
+    /// Restore the previous keys and buttons replaced by latch
+    pub(crate) fn unlatch(&mut self, latched: LatchedInput) {
+        self.replace_previous(latched);
+    }
+
+    /// Compare keys and buttons against the ones at the end of the last fixed update,
+    /// so presses and releases on frames without a fixed update are not missed
+    ///
+    /// Returns the previous state of this frame to restore with end_fixed_update
+    pub(crate) fn begin_fixed_update(&mut self) -> LatchedInput {
+        match self.fixed_previous.take() {
+            Some(fixed_previous) => self.replace_previous(fixed_previous),
+            None => self.previous(),
+        }
+    }
+
+    /// Remember the keys and buttons seen by the last fixed update
+    /// and restore the previous state of this frame
+    pub(crate) fn end_fixed_update(&mut self, frame_previous: LatchedInput) {
+        self.fixed_previous = Some(self.current());
+        self.unlatch(frame_previous);
+    }
+
+    /// Copy of the previous keys and buttons, leaving them in place
+    fn previous(&self) -> LatchedInput {
+        LatchedInput {
+            keys: self.keyboard.previous_pressed.clone(),
+            logical_keys: self.keyboard.previous_logical_pressed.clone(),
+            modifiers: self.keyboard.previous_pressed_modifiers.clone(),
+            mouse_buttons: self.mouse.previous_pressed.clone(),
+            gamepad_buttons: self
+                .gamepad
+                .slots
+                .iter()
+                .map(|slot| slot.as_ref().map(|state| state.previous_pressed.clone()))
+                .collect(),
+        }
+    }
+
+    /// Poll gamepads and queue their events among the others by the time they happened
+    pub(crate) fn poll_gamepads(&mut self) {
+        for (time, kind) in self.gamepad.poll() {
+            let index = self.events.partition_point(|event| event.time <= time);
+            self.events.insert(index, InputEvent { time, kind });
+        }
+    }
+
+    /// Update a touch and queue the event, emulating the left mouse button if enabled
+    pub(crate) fn touch(
+        &mut self,
+        id: u64,
+        phase: TouchPhase,
+        pos: (f64, f64),
+        force: Option<f32>,
+    ) {
+        self.touch.update(id, phase, pos, force);
+        self.record(InputEventKind::Touch {
+            id,
+            phase,
+            x: pos.0,
+            y: pos.1,
+            force,
+        });
+
+        if !self.touch.emulate_mouse {
+            return;
+        }
+        // Only the first finger down moves the mouse
+        match phase {
+            TouchPhase::Started if self.touch.mouse_touch.is_none() => {
+                self.touch.mouse_touch = Some(id);
+                self.mouse.set_on_screen(true);
+                self.mouse.set_pos(pos.0, pos.1);
+                self.record(InputEventKind::MouseMoved { x: pos.0, y: pos.1 });
+                self.mouse.press_button(MouseButton::Left);
+                self.record(InputEventKind::MouseButtonPressed(MouseButton::Left));
+            }
+            TouchPhase::Moved if self.touch.mouse_touch == Some(id) => {
+                self.mouse.set_pos(pos.0, pos.1);
+                self.record(InputEventKind::MouseMoved { x: pos.0, y: pos.1 });
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled if self.touch.mouse_touch == Some(id) => {
+                self.touch.mouse_touch = None;
+                self.mouse.set_pos(pos.0, pos.1);
+                self.record(InputEventKind::MouseMoved { x: pos.0, y: pos.1 });
+                self.mouse.release_button(MouseButton::Left);
+                self.record(InputEventKind::MouseButtonReleased(MouseButton::Left));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Canvas pixel at physical window coordinates
+fn pixel_at(pos: (f64, f64), ctx: &RenderContext) -> (u32, u32) {
+    // When holding the mouse button down pos can get bigger than physical size
+    // So clamp to avoid out of bounds
+    let (window_width, window_height) = ctx.window_size();
+    let relative_x = pos.0 / window_width as f64;
+    let relative_y = pos.1 / window_height as f64;
+    let pixel_x = relative_x * ctx.canvas.width as f64;
+    let pixel_y = relative_y * ctx.canvas.height as f64;
+    // Negative positions saturate to 0 when cast
+    (
+        (pixel_x as u32).min(ctx.canvas.width.saturating_sub(1)),
+        (pixel_y as u32).min(ctx.canvas.height.saturating_sub(1)),
+    )
+}
+
+/// Snapshot of pressed keys and buttons, put aside while input is latched
+pub(crate) struct LatchedInput {
+    keys: HashSet<KeyCode>,
+    logical_keys: HashSet<Key>,
+    modifiers: HashSet<KeyModifier>,
+    mouse_buttons: HashSet<MouseButton>,
+    gamepad_buttons: Vec<Option<HashSet<GamepadButton>>>,
+}
+
+fn keycode(physical: PhysicalKey) -> Option<KeyCode> {
+    match physical {
+        PhysicalKey::Code(keycode) => Some(keycode),
+        PhysicalKey::Unidentified(_) => None,
+    }
 }
 
 #[derive(Default)]
@@ -20,6 +332,7 @@ pub(crate) struct MouseContext {
     pressed: HashSet<MouseButton>,
     previous_pressed: HashSet<MouseButton>,
     scroll_delta: (f64, f64),
+    gestures: MouseGestures,
 }
 
 impl MouseContext {
@@ -52,14 +365,7 @@ impl MouseContext {
 
     /// Returns the current pixel under the mouse
     fn mouse_pos_pixel(&self, ctx: &RenderContext) -> (u32, u32) {
-        // When holding the mouse button down pos can get bigger than physical size
-        // So clamp to avoid out of bounds
-        let window_size = ctx.window.inner_size();
-        let relative_x = self.pos.0 / window_size.width as f64;
-        let relative_y = self.pos.1 / window_size.height as f64;
-        let pixel_x = relative_x * ctx.canvas.width as f64;
-        let pixel_y = relative_y * ctx.canvas.height as f64;
-        (pixel_x as u32, pixel_y as u32)
+        pixel_at(self.pos, ctx)
     }
 
     /// Returns the (dx, dy) change in mouse position
@@ -88,6 +394,12 @@ impl MouseContext {
         self.mouse_delta = change;
     }
 
+    /// Adds to the (dx, dy) change in mouse position, as several motions can arrive per frame
+    pub(crate) fn add_mouse_delta(&mut self, change: (f64, f64)) {
+        self.mouse_delta.0 += change.0;
+        self.mouse_delta.1 += change.1;
+    }
+
     pub(crate) fn set_scroll_delta(&mut self, change: (f64, f64)) {
         self.scroll_delta = change;
     }
@@ -109,6 +421,257 @@ impl MouseContext {
     }
 }
 
+/// Thresholds for recognizing mouse gestures
+///
+/// Distances are in canvas pixels
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GestureThresholds {
+    /// Longest time in seconds between the two presses of a double click
+    pub double_click_time: f32,
+    /// Farthest distance between the two presses of a double click
+    pub double_click_distance: u32,
+    /// Distance the mouse moves while a button is held before a drag starts
+    pub drag_distance: u32,
+    /// Time in seconds a button is held without dragging before a long press
+    pub long_press_time: f32,
+}
+
+impl Default for GestureThresholds {
+    fn default() -> Self {
+        Self {
+            double_click_time: 0.3,
+            double_click_distance: 2,
+            drag_distance: 2,
+            long_press_time: 0.5,
+        }
+    }
+}
+
+/// A drag from where the button was pressed, in canvas pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Drag {
+    pub start: (u32, u32),
+    pub end: (u32, u32),
+}
+
+struct HeldButton {
+    time: f32,
+    start: (u32, u32),
+    pos: (u32, u32),
+    dragging: bool,
+    long_pressed: bool,
+}
+
+#[derive(Default)]
+pub(crate) struct MouseGestures {
+    thresholds: GestureThresholds,
+    // physical position as of the last processed event
+    pos: (f64, f64),
+    held: HashMap<MouseButton, HeldButton>,
+    last_press: HashMap<MouseButton, (f32, (u32, u32))>,
+    // gestures recognized this frame
+    double_clicked: HashSet<MouseButton>,
+    long_pressed: HashSet<MouseButton>,
+    drag_started: HashSet<MouseButton>,
+    drag_delta: HashMap<MouseButton, (i32, i32)>,
+    drag_ended: HashMap<MouseButton, Drag>,
+}
+
+impl MouseGestures {
+    /// Recognize gestures from this frame's events, now being the time since start
+    fn update(&mut self, now: f32, events: &[InputEvent], render: &RenderContext) {
+        self.double_clicked.clear();
+        self.long_pressed.clear();
+        self.drag_started.clear();
+        self.drag_delta.clear();
+        self.drag_ended.clear();
+
+        for event in events {
+            match event.kind {
+                InputEventKind::MouseMoved { x, y } => {
+                    self.pos = (x, y);
+                    self.moved(pixel_at(self.pos, render));
+                }
+                InputEventKind::MouseButtonPressed(button) => {
+                    self.pressed(button, now, pixel_at(self.pos, render))
+                }
+                InputEventKind::MouseButtonReleased(button) => self.released(button),
+                _ => {}
+            }
+        }
+
+        for (button, held) in &mut self.held {
+            if !held.dragging
+                && !held.long_pressed
+                && now - held.time >= self.thresholds.long_press_time
+            {
+                held.long_pressed = true;
+                self.long_pressed.insert(*button);
+            }
+        }
+    }
+
+    fn pressed(&mut self, button: MouseButton, now: f32, pos: (u32, u32)) {
+        let double_click = self
+            .last_press
+            .remove(&button)
+            .is_some_and(|(time, start)| {
+                now - time <= self.thresholds.double_click_time
+                    && distance_squared(start, pos) <= self.thresholds.double_click_distance.pow(2)
+            });
+        if double_click {
+            self.double_clicked.insert(button);
+        } else {
+            self.last_press.insert(button, (now, pos));
+        }
+
+        self.held.insert(
+            button,
+            HeldButton {
+                time: now,
+                start: pos,
+                pos,
+                dragging: false,
+                long_pressed: false,
+            },
+        );
+    }
+
+    fn moved(&mut self, pos: (u32, u32)) {
+        for (button, held) in &mut self.held {
+            if !held.dragging
+                && distance_squared(held.start, pos) > self.thresholds.drag_distance.pow(2)
+            {
+                held.dragging = true;
+                // So the deltas of a drag add up to its full length
+                held.pos = held.start;
+                self.drag_started.insert(*button);
+                self.last_press.remove(button);
+            }
+            if held.dragging {
+                let delta = self.drag_delta.entry(*button).or_default();
+                delta.0 += pos.0 as i32 - held.pos.0 as i32;
+                delta.1 += pos.1 as i32 - held.pos.1 as i32;
+            }
+            held.pos = pos;
+        }
+    }
+
+    fn released(&mut self, button: MouseButton) {
+        if let Some(held) = self.held.remove(&button) {
+            if held.dragging {
+                self.drag_ended.insert(
+                    button,
+                    Drag {
+                        start: held.start,
+                        end: held.pos,
+                    },
+                );
+            }
+        }
+    }
+}
+
+fn distance_squared(a: (u32, u32), b: (u32, u32)) -> u32 {
+    a.0.abs_diff(b.0).pow(2) + a.1.abs_diff(b.1).pow(2)
+}
+
+/// Recognize mouse gestures from this frame's events
+///
+/// Called once per frame before the user callbacks
+pub(crate) fn update_gestures(ctx: &mut Context) {
+    let now = time::time_since_start(ctx);
+    ctx.input
+        .mouse
+        .gestures
+        .update(now, &ctx.input.events, &ctx.render);
+}
+
+/// A finger or pen on the screen
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Touch {
+    pub id: u64,
+    /// Started on the frame the touch began, Ended or Cancelled on the frame it lifted
+    /// and Moved in between
+    ///
+    /// A touch lifted on the frame it began is Started, then Ended or Cancelled the next frame
+    pub phase: TouchPhase,
+    /// Current position in canvas pixels
+    pub pos: (u32, u32),
+    /// Where the touch began in canvas pixels
+    pub start: (u32, u32),
+    /// Pressure \[0,1\], None if the device does not report it
+    pub force: Option<f32>,
+}
+
+struct TouchState {
+    phase: TouchPhase,
+    pos: (f64, f64),
+    start: (f64, f64),
+    force: Option<f32>,
+    // Ended or Cancelled on the frame the touch started, reported next frame
+    lifted: Option<TouchPhase>,
+}
+
+#[derive(Default)]
+pub(crate) struct TouchContext {
+    // ordered by id so queries are stable between frames
+    touches: BTreeMap<u64, TouchState>,
+    emulate_mouse: bool,
+    // touch acting as the left mouse button
+    mouse_touch: Option<u64>,
+}
+
+impl TouchContext {
+    fn update(&mut self, id: u64, phase: TouchPhase, pos: (f64, f64), force: Option<f32>) {
+        match self.touches.get_mut(&id) {
+            Some(touch) => {
+                // Keep Started until the frame ends so a quick tap or move is not missed
+                if touch.phase != TouchPhase::Started {
+                    touch.phase = phase;
+                } else if phase != TouchPhase::Moved {
+                    touch.lifted = Some(phase);
+                }
+                touch.pos = pos;
+                touch.force = force;
+            }
+            None => {
+                self.touches.insert(
+                    id,
+                    TouchState {
+                        phase,
+                        pos,
+                        start: pos,
+                        force,
+                        lifted: None,
+                    },
+                );
+            }
+        }
+    }
+
+    /// Forget lifted touches and mark the rest as held
+    /// Should be called each frame
+    pub(crate) fn save_touches(&mut self) {
+        self.touches
+            .retain(|_, touch| !matches!(touch.phase, TouchPhase::Ended | TouchPhase::Cancelled));
+        for touch in self.touches.values_mut() {
+            touch.phase = touch.lifted.take().unwrap_or(TouchPhase::Moved);
+        }
+    }
+}
+
+//
+// Event commands
+//
+
+/// Input events this frame in the order they happened
+///
+/// Unlike the pressed queries, a key pressed and released within one frame shows up here
+pub fn events(ctx: &Context) -> impl Iterator<Item = &InputEvent> {
+    ctx.input.events.iter()
+}
+
 //
 // Mouse commands
 //
@@ -156,12 +719,138 @@ pub fn scroll_delta(ctx: &Context) -> (f32, f32) {
     (dx as f32, dy as f32)
 }
 
+/// If MouseButton was pressed twice in quick succession this frame
+pub fn mouse_double_clicked(ctx: &Context, button: MouseButton) -> bool {
+    ctx.input.mouse.gestures.double_clicked.contains(&button)
+}
+
+/// If MouseButton has been held in place long enough this frame
+///
+/// Fires once per press, not while dragging
+pub fn mouse_long_pressed(ctx: &Context, button: MouseButton) -> bool {
+    ctx.input.mouse.gestures.long_pressed.contains(&button)
+}
+
+/// If a drag with MouseButton started this frame
+pub fn mouse_drag_started(ctx: &Context, button: MouseButton) -> bool {
+    ctx.input.mouse.gestures.drag_started.contains(&button)
+}
+
+/// Drag in progress with MouseButton, ending at the current mouse pixel
+pub fn mouse_drag(ctx: &Context, button: MouseButton) -> Option<Drag> {
+    ctx.input
+        .mouse
+        .gestures
+        .held
+        .get(&button)
+        .filter(|held| held.dragging)
+        .map(|held| Drag {
+            start: held.start,
+            end: held.pos,
+        })
+}
+
+/// Canvas pixels a drag with MouseButton moved this frame
+pub fn mouse_drag_delta(ctx: &Context, button: MouseButton) -> (i32, i32) {
+    ctx.input
+        .mouse
+        .gestures
+        .drag_delta
+        .get(&button)
+        .copied()
+        .unwrap_or_default()
+}
+
+/// Drag with MouseButton released this frame
+pub fn mouse_drag_ended(ctx: &Context, button: MouseButton) -> Option<Drag> {
+    ctx.input.mouse.gestures.drag_ended.get(&button).copied()
+}
+
+/// Sets the thresholds for double clicks, drags and long presses
+pub fn set_gesture_thresholds(ctx: &mut Context, thresholds: GestureThresholds) {
+    ctx.input.mouse.gestures.thresholds = thresholds;
+}
+
+/// Current thresholds for double clicks, drags and long presses
+pub fn gesture_thresholds(ctx: &Context) -> GestureThresholds {
+    ctx.input.mouse.gestures.thresholds
+}
+
+//
+// Touch commands
+//
+
+/// Touches this frame ordered by id, including those lifted this frame
+///
+/// Pens are reported as touches, usually with force
+pub fn touches(ctx: &Context) -> impl Iterator<Item = Touch> + '_ {
+    ctx.input.touch.touches.iter().map(|(&id, touch)| Touch {
+        id,
+        phase: touch.phase,
+        pos: pixel_at(touch.pos, &ctx.render),
+        start: pixel_at(touch.start, &ctx.render),
+        force: touch.force,
+    })
+}
+
+/// Touch with id if it is on the screen or was lifted this frame
+pub fn touch(ctx: &Context, id: u64) -> Option<Touch> {
+    touches(ctx).find(|touch| touch.id == id)
+}
+
+/// Number of fingers or pens on the screen
+pub fn touch_count(ctx: &Context) -> usize {
+    touches(ctx)
+        .filter(|touch| matches!(touch.phase, TouchPhase::Started | TouchPhase::Moved))
+        .count()
+}
+
+/// Let the first touch act as the left mouse button, so mouse based code works on touch screens
+///
+/// Off by default
+pub fn set_touch_mouse_emulation(ctx: &mut Context, enabled: bool) {
+    ctx.input.touch.emulate_mouse = enabled;
+}
+
 #[derive(Default)]
 pub(crate) struct KeyboardContext {
     pressed: HashSet<KeyCode>,
     previous_pressed: HashSet<KeyCode>,
+    logical_pressed: HashSet<Key>,
+    previous_logical_pressed: HashSet<Key>,
+    // logical key each held physical key produced when pressed
+    logical_keys: HashMap<PhysicalKey, Key>,
     pressed_modifiers: HashSet<KeyModifier>,
     previous_pressed_modifiers: HashSet<KeyModifier>,
+    repeat: KeyRepeat,
+    // seconds each pressed key has been held
+    held_time: HashMap<KeyCode, f32>,
+    repeated: HashSet<KeyCode>,
+}
+
+struct KeyRepeat {
+    delay: f32,
+    interval: f32,
+}
+
+impl Default for KeyRepeat {
+    fn default() -> Self {
+        Self {
+            delay: 0.4,
+            interval: 0.05,
+        }
+    }
+}
+
+impl KeyRepeat {
+    /// Times a key held for seconds has repeated, counting the initial press
+    fn count(&self, seconds: f32) -> u32 {
+        if seconds < self.delay {
+            1
+        } else {
+            ((seconds - self.delay) / self.interval) as u32 + 2
+        }
+    }
 }
 
 /// Describes key modifiers
@@ -192,6 +881,22 @@ impl KeyboardContext {
         !self.pressed.contains(&keycode) && self.previous_pressed.contains(&keycode)
     }
 
+    fn logical_key_pressed(&self, key: &Key) -> bool {
+        self.logical_pressed.contains(key)
+    }
+
+    fn logical_key_just_pressed(&self, key: &Key) -> bool {
+        self.logical_pressed.contains(key) && !self.previous_logical_pressed.contains(key)
+    }
+
+    fn logical_key_released(&self, key: &Key) -> bool {
+        !self.logical_pressed.contains(key) && self.previous_logical_pressed.contains(key)
+    }
+
+    fn key_repeated(&self, keycode: KeyCode) -> bool {
+        self.repeated.contains(&keycode)
+    }
+
     fn modifier_pressed(&self, modifier: KeyModifier) -> bool {
         self.pressed_modifiers.contains(&modifier)
     }
@@ -218,6 +923,40 @@ impl KeyboardContext {
         self.pressed.remove(&keycode);
     }
 
+    /// Sets a physical key and the logical key it produced for current frame
+    pub(crate) fn press(&mut self, physical: PhysicalKey, logical: Key) {
+        if let PhysicalKey::Code(keycode) = physical {
+            self.set_key(keycode);
+        }
+
+        let logical = normalize_logical_key(logical);
+        if let Some(previous) = self.logical_keys.insert(physical, logical.clone()) {
+            if previous != logical {
+                self.release_logical_key(previous);
+            }
+        }
+        self.logical_pressed.insert(logical);
+    }
+
+    /// Release a physical key and the logical key it produced when pressed
+    ///
+    /// The logical key may differ on release, e.g. if shift was released first
+    pub(crate) fn release(&mut self, physical: PhysicalKey) {
+        if let PhysicalKey::Code(keycode) = physical {
+            self.release_key(keycode);
+        }
+        if let Some(logical) = self.logical_keys.remove(&physical) {
+            self.release_logical_key(logical);
+        }
+    }
+
+    /// Release a logical key unless another held physical key produced it
+    fn release_logical_key(&mut self, logical: Key) {
+        if !self.logical_keys.values().any(|held| *held == logical) {
+            self.logical_pressed.remove(&logical);
+        }
+    }
+
     pub fn modifiers_changed(&mut self, state: &Modifiers) {
         self.pressed_modifiers.clear();
 
@@ -235,10 +974,34 @@ impl KeyboardContext {
         }
     }
 
+    /// Advance how long keys have been held and find the ones repeating this frame
+    /// Should be called each frame before the user callbacks
+    pub(crate) fn update_repeat(&mut self, delta_time: f32) {
+        self.repeated.clear();
+        self.held_time
+            .retain(|keycode, _| self.pressed.contains(keycode));
+        for &keycode in &self.pressed {
+            match self.held_time.get_mut(&keycode) {
+                Some(held) => {
+                    let before = self.repeat.count(*held);
+                    *held += delta_time;
+                    if self.repeat.count(*held) > before {
+                        self.repeated.insert(keycode);
+                    }
+                }
+                None => {
+                    self.held_time.insert(keycode, 0.0);
+                    self.repeated.insert(keycode);
+                }
+            }
+        }
+    }
+
     /// Save current keys in previous
     /// Should be called each frame
     pub(crate) fn save_keys(&mut self) {
         self.previous_pressed = self.pressed.clone();
+        self.previous_logical_pressed = self.logical_pressed.clone();
     }
 
     /// Save current keys modifiers in previous
@@ -248,6 +1011,16 @@ impl KeyboardContext {
     }
 }
 
+/// Lowercase characters so shortcuts match regardless of shift
+fn normalize_logical_key(key: Key) -> Key {
+    match key {
+        Key::Character(c) if c.chars().any(char::is_uppercase) => {
+            Key::Character(c.to_lowercase().into())
+        }
+        key => key,
+    }
+}
+
 //
 // Keyboard commands
 //
@@ -269,6 +1042,61 @@ pub fn key_released(ctx: &Context, keycode: KeyCode) -> bool {
     ctx.input.keyboard.key_released(keycode)
 }
 
+/// If KeyCode was pressed this frame while exactly these modifiers are held,
+/// e.g. Ctrl+Shift+S but not Ctrl+S or Ctrl+Alt+Shift+S
+pub fn shortcut_just_pressed(ctx: &Context, modifiers: &[KeyModifier], keycode: KeyCode) -> bool {
+    let modifiers = modifiers.iter().copied().collect::<HashSet<_>>();
+    ctx.input.keyboard.key_just_pressed(keycode)
+        && ctx.input.keyboard.pressed_modifiers == modifiers
+}
+
+/// If KeyCode was pressed this frame or repeats while held, for menus and text fields
+///
+/// Repeats after the delay then at the interval set with set_key_repeat,
+/// counted in real frame time rather than by the OS
+pub fn key_repeated(ctx: &Context, keycode: KeyCode) -> bool {
+    ctx.input.keyboard.key_repeated(keycode)
+}
+
+/// Sets the seconds before a held key repeats and between repeats
+pub fn set_key_repeat(ctx: &mut Context, delay: f32, interval: f32) {
+    ctx.input.keyboard.repeat = KeyRepeat {
+        delay: delay.max(0.0),
+        interval: interval.max(0.001),
+    };
+}
+
+/// If the logical key, which the keyboard layout produced, is pressed
+///
+/// Characters are lowercase so they match with shift held,
+/// e.g. `Key::Character("z".into())` for Ctrl+Z on any layout.
+/// Use KeyCode queries for keys chosen by position such as WASD
+pub fn logical_key_pressed(ctx: &Context, key: &Key) -> bool {
+    ctx.input.keyboard.logical_key_pressed(key)
+}
+
+/// If the logical key was pressed this frame
+pub fn logical_key_just_pressed(ctx: &Context, key: &Key) -> bool {
+    ctx.input.keyboard.logical_key_just_pressed(key)
+}
+
+/// If the logical key was released this frame
+pub fn logical_key_released(ctx: &Context, key: &Key) -> bool {
+    ctx.input.keyboard.logical_key_released(key)
+}
+
+/// If a key producing the character is pressed, case insensitive
+pub fn character_pressed(ctx: &Context, character: char) -> bool {
+    let key = normalize_logical_key(Key::Character(character.to_string().into()));
+    ctx.input.keyboard.logical_key_pressed(&key)
+}
+
+/// If a key producing the character was pressed this frame, case insensitive
+pub fn character_just_pressed(ctx: &Context, character: char) -> bool {
+    let key = normalize_logical_key(Key::Character(character.to_string().into()));
+    ctx.input.keyboard.logical_key_just_pressed(&key)
+}
+
 /// If KeyModifer is pressed
 ///
 /// Accepts repeating
@@ -286,18 +1114,731 @@ pub fn modifer_released(ctx: &Context, key_modifier: KeyModifier) -> bool {
     ctx.input.keyboard.modifier_released(key_modifier)
 }
 
+/// Text editing input in the order it was typed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TextEdit {
+    /// Characters typed or committed by an input method
+    Insert(String),
+    /// Remove the character before the cursor
+    Backspace,
+    /// Remove the character after the cursor
+    Delete,
+    /// Enter was pressed
+    Submit,
+}
+
+impl TextEdit {
+    /// Apply the edit to the end of a string
+    ///
+    /// Returns true if the edit was a submit
+    pub fn apply(&self, text: &mut String) -> bool {
+        let mut cursor = text.len();
+        self.apply_at(text, &mut cursor)
+    }
+
+    /// Apply the edit at cursor, a byte index into text, and move the cursor along
+    ///
+    /// Returns true if the edit was a submit
+    pub fn apply_at(&self, text: &mut String, cursor: &mut usize) -> bool {
+        *cursor = (*cursor).min(text.len());
+        while !text.is_char_boundary(*cursor) {
+            *cursor -= 1;
+        }
+        match self {
+            TextEdit::Insert(inserted) => {
+                text.insert_str(*cursor, inserted);
+                *cursor += inserted.len();
+            }
+            TextEdit::Backspace => {
+                if let Some(c) = text[..*cursor].chars().next_back() {
+                    *cursor -= c.len_utf8();
+                    text.remove(*cursor);
+                }
+            }
+            TextEdit::Delete => {
+                if *cursor < text.len() {
+                    text.remove(*cursor);
+                }
+            }
+            TextEdit::Submit => return true,
+        }
+        false
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct TextContext {
+    text: String,
+    edits: Vec<TextEdit>,
+    preedit: Option<(String, Option<(usize, usize)>)>,
+    pub clipboard: Clipboard,
+}
+
+/// System clipboard, or a clipboard within the app if there is none
+#[derive(Default)]
+pub(crate) struct Clipboard {
+    system: Option<arboard::Clipboard>,
+    local: String,
+}
+
+impl Clipboard {
+    /// Use the system clipboard from now on if there is one
+    pub(crate) fn connect(&mut self) {
+        match arboard::Clipboard::new() {
+            Ok(system) => self.system = Some(system),
+            Err(err) => log::warn!("could not access the clipboard: {err}"),
+        }
+    }
+
+    /// Text on the clipboard, empty if it holds none
+    fn text(&mut self) -> String {
+        match &mut self.system {
+            Some(system) => system.get_text().unwrap_or_default(),
+            None => self.local.clone(),
+        }
+    }
+
+    fn set_text(&mut self, text: &str) {
+        match &mut self.system {
+            Some(system) => {
+                if let Err(err) = system.set_text(text) {
+                    log::warn!("could not copy to the clipboard: {err}");
+                }
+            }
+            None => self.local = text.to_string(),
+        }
+    }
+}
+
+impl TextContext {
+    /// Adds typed text, skipping control characters
+    ///
+    /// Returns the added text
+    pub(crate) fn insert(&mut self, text: &str) -> String {
+        let text = text.chars().filter(|c| !c.is_control()).collect::<String>();
+        if text.is_empty() {
+            return text;
+        }
+        self.text.push_str(&text);
+        match self.edits.last_mut() {
+            Some(TextEdit::Insert(last)) => last.push_str(&text),
+            _ => self.edits.push(TextEdit::Insert(text.clone())),
+        }
+        text
+    }
+
+    /// Adds an editing key press, including repeats
+    pub(crate) fn edit(&mut self, edit: TextEdit) {
+        self.edits.push(edit);
+    }
+
+    /// Sets the text being composed by an input method
+    pub(crate) fn set_preedit(&mut self, text: &str, cursor: Option<(usize, usize)>) {
+        self.preedit = (!text.is_empty()).then(|| (text.to_string(), cursor));
+    }
+
+    /// Clear text typed this frame
+    /// Should be called each frame
+    pub(crate) fn clear(&mut self) {
+        self.text.clear();
+        self.edits.clear();
+    }
+}
+
+/// If a key typed with these modifiers is a shortcut rather than text
+///
+/// Ctrl with Alt is AltGr on Windows and Option types characters on macOS,
+/// so only Ctrl without Alt is left out, and never on macOS
+pub(crate) fn is_shortcut(ctrl: bool, alt: bool) -> bool {
+    ctrl && !alt && !cfg!(target_os = "macos")
+}
+
+/// If V typed with these modifiers pastes, Cmd+V on macOS and Ctrl+V elsewhere
+pub(crate) fn is_paste_shortcut(ctrl: bool, alt: bool, command: bool) -> bool {
+    if cfg!(target_os = "macos") {
+        command && !ctrl && !alt
+    } else {
+        is_shortcut(ctrl, alt)
+    }
+}
+
+//
+// Text commands
+//
+
+/// Text typed this frame
+///
+/// Follows the keyboard layout, shift state, dead keys and input methods.
+/// Control characters such as backspace, enter and tab are left out, see [`text_edits`].
+/// Includes text pasted with Ctrl+V (Cmd+V on macOS) in a window,
+/// or sent by the terminal as bracketed paste
+pub fn text_input(ctx: &Context) -> &str {
+    &ctx.input.text.text
+}
+
+/// Text and editing keys this frame in the order they were typed
+///
+/// Key repeats produce one edit each
+pub fn text_edits(ctx: &Context) -> &[TextEdit] {
+    &ctx.input.text.edits
+}
+
+/// Applies this frame's typing to the end of text
+///
+/// Returns true if enter was pressed
+pub fn edit_text(ctx: &Context, text: &mut String) -> bool {
+    let mut cursor = text.len();
+    edit_text_at(ctx, text, &mut cursor)
+}
+
+/// Applies this frame's typing at cursor, a byte index into text
+///
+/// Returns true if enter was pressed
+pub fn edit_text_at(ctx: &Context, text: &mut String, cursor: &mut usize) -> bool {
+    let mut submitted = false;
+    for edit in &ctx.input.text.edits {
+        submitted |= edit.apply_at(text, cursor);
+    }
+    submitted
+}
+
+/// Text being composed by an input method and the selected range within it in bytes
+///
+/// Not yet part of [`text_input`], which receives it once committed
+pub fn ime_preedit(ctx: &Context) -> Option<(&str, Option<(usize, usize)>)> {
+    ctx.input
+        .text
+        .preedit
+        .as_ref()
+        .map(|(text, cursor)| (text.as_str(), *cursor))
+}
+
+/// Copies text to the clipboard
+///
+/// Uses a clipboard within the app if the system clipboard is unavailable
+pub fn copy_text(ctx: &mut Context, text: &str) {
+    ctx.input.text.clipboard.set_text(text);
+}
+
+/// Text on the clipboard, empty if it holds none
+pub fn clipboard_text(ctx: &mut Context) -> String {
+    ctx.input.text.clipboard.text()
+}
+
+/// Gamepad buttons, named by position on the controller
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum GamepadButton {
+    /// Bottom face button, A on Xbox controllers
+    South,
+    /// Right face button, B on Xbox controllers
+    East,
+    /// Top face button, Y on Xbox controllers
+    North,
+    /// Left face button, X on Xbox controllers
+    West,
+    LeftBumper,
+    RightBumper,
+    LeftStick,
+    RightStick,
+    Select,
+    Start,
+    Mode,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+/// Gamepad analog inputs
+///
+/// Sticks are in range \[-1,1\] with up being positive, triggers in range \[0,1\]
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum GamepadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+const GAMEPAD_AXES: usize = 6;
+const DEFAULT_STICK_DEAD_ZONE: f32 = 0.15;
+const DEFAULT_TRIGGER_DEAD_ZONE: f32 = 0.05;
+
+/// Event reported by a gamepad backend
+///
+/// Ids are chosen by the backend and only need to be unique among connected gamepads
+#[derive(Debug, Clone, PartialEq)]
+pub enum GamepadEvent {
+    Connected {
+        id: u64,
+        name: String,
+    },
+    Disconnected {
+        id: u64,
+    },
+    ButtonPressed {
+        id: u64,
+        button: GamepadButton,
+    },
+    ButtonReleased {
+        id: u64,
+        button: GamepadButton,
+    },
+    AxisChanged {
+        id: u64,
+        axis: GamepadAxis,
+        value: f32,
+    },
+}
+
+/// Source of gamepad events, polled once per frame
+///
+/// Implement to support other libraries or to inject input in tests
+pub trait GamepadBackend {
+    /// Push all events since the last poll with the time they happened
+    fn poll(&mut self, events: &mut Vec<(Instant, GamepadEvent)>);
+}
+
+pub(crate) struct GamepadContext {
+    backend: Option<Box<dyn GamepadBackend>>,
+    events: Vec<(Instant, GamepadEvent)>,
+    slots: Vec<Option<GamepadState>>,
+    just_connected: Vec<usize>,
+    just_disconnected: Vec<usize>,
+    stick_dead_zone: f32,
+    trigger_dead_zone: f32,
+}
+
+struct GamepadState {
+    id: u64,
+    name: String,
+    pressed: HashSet<GamepadButton>,
+    previous_pressed: HashSet<GamepadButton>,
+    axes: [f32; GAMEPAD_AXES],
+}
+
+impl Default for GamepadContext {
+    fn default() -> Self {
+        Self {
+            backend: default_gamepad_backend(),
+            events: Vec::new(),
+            slots: Vec::new(),
+            just_connected: Vec::new(),
+            just_disconnected: Vec::new(),
+            stick_dead_zone: DEFAULT_STICK_DEAD_ZONE,
+            trigger_dead_zone: DEFAULT_TRIGGER_DEAD_ZONE,
+        }
+    }
+}
+
+#[cfg(feature = "gilrs")]
+fn default_gamepad_backend() -> Option<Box<dyn GamepadBackend>> {
+    match GilrsBackend::new() {
+        Ok(backend) => Some(Box::new(backend)),
+        Err(err) => {
+            log::warn!("could not initialize gamepads: {err}");
+            None
+        }
+    }
+}
+
+#[cfg(not(feature = "gilrs"))]
+fn default_gamepad_backend() -> Option<Box<dyn GamepadBackend>> {
+    None
+}
+
+impl GamepadContext {
+    fn state(&self, slot: usize) -> Option<&GamepadState> {
+        self.slots.get(slot).and_then(Option::as_ref)
+    }
+
+    fn button_pressed(&self, slot: usize, button: GamepadButton) -> bool {
+        self.state(slot)
+            .is_some_and(|state| state.pressed.contains(&button))
+    }
+
+    fn button_just_pressed(&self, slot: usize, button: GamepadButton) -> bool {
+        self.state(slot).is_some_and(|state| {
+            state.pressed.contains(&button) && !state.previous_pressed.contains(&button)
+        })
+    }
+
+    fn button_released(&self, slot: usize, button: GamepadButton) -> bool {
+        self.state(slot).is_some_and(|state| {
+            !state.pressed.contains(&button) && state.previous_pressed.contains(&button)
+        })
+    }
+
+    /// Axis value with dead zones applied
+    ///
+    /// Sticks use a radial dead zone so diagonals are not snapped to the axes
+    fn axis(&self, slot: usize, axis: GamepadAxis) -> f32 {
+        let Some(state) = self.state(slot) else {
+            return 0.0;
+        };
+        let value = |axis: GamepadAxis| state.axes[axis as usize];
+
+        let (x, y, is_x) = match axis {
+            GamepadAxis::LeftTrigger | GamepadAxis::RightTrigger => {
+                return apply_dead_zone(value(axis), self.trigger_dead_zone);
+            }
+            GamepadAxis::LeftStickX => (GamepadAxis::LeftStickX, GamepadAxis::LeftStickY, true),
+            GamepadAxis::LeftStickY => (GamepadAxis::LeftStickX, GamepadAxis::LeftStickY, false),
+            GamepadAxis::RightStickX => (GamepadAxis::RightStickX, GamepadAxis::RightStickY, true),
+            GamepadAxis::RightStickY => (GamepadAxis::RightStickX, GamepadAxis::RightStickY, false),
+        };
+        let (x, y) = (value(x), value(y));
+        let length = (x * x + y * y).sqrt();
+        if length <= self.stick_dead_zone {
+            return 0.0;
+        }
+        let scale = apply_dead_zone(length.min(1.0), self.stick_dead_zone) / length;
+        if is_x {
+            x * scale
+        } else {
+            y * scale
+        }
+    }
+
+    /// Poll the backend and apply its events
+    ///
+    /// Returns the applied events
+    pub(crate) fn poll(&mut self) -> Vec<(Instant, InputEventKind)> {
+        if let Some(backend) = &mut self.backend {
+            backend.poll(&mut self.events);
+        }
+        let events = std::mem::take(&mut self.events);
+        let applied = events
+            .iter()
+            .filter_map(|(time, event)| Some((*time, self.handle_event(event)?)))
+            .collect();
+        self.events = events;
+        self.events.clear();
+        applied
+    }
+
+    /// Apply an event, returning it with the slot of its gamepad
+    ///
+    /// Events of unknown gamepads are ignored
+    pub(crate) fn handle_event(&mut self, event: &GamepadEvent) -> Option<InputEventKind> {
+        let kind = match event {
+            GamepadEvent::Connected { id, name } => {
+                if self.slot_of(*id).is_some() {
+                    return None;
+                }
+                let state = GamepadState {
+                    id: *id,
+                    name: name.clone(),
+                    pressed: HashSet::new(),
+                    previous_pressed: HashSet::new(),
+                    axes: [0.0; GAMEPAD_AXES],
+                };
+                let slot = match self.slots.iter().position(Option::is_none) {
+                    Some(slot) => slot,
+                    None => {
+                        self.slots.push(None);
+                        self.slots.len() - 1
+                    }
+                };
+                self.slots[slot] = Some(state);
+                self.just_connected.push(slot);
+                InputEventKind::GamepadConnected { slot }
+            }
+            GamepadEvent::Disconnected { id } => {
+                let slot = self.slot_of(*id)?;
+                self.slots[slot] = None;
+                self.just_disconnected.push(slot);
+                InputEventKind::GamepadDisconnected { slot }
+            }
+            GamepadEvent::ButtonPressed { id, button } => {
+                let slot = self.slot_of(*id)?;
+                self.slots[slot].as_mut()?.pressed.insert(*button);
+                InputEventKind::GamepadButtonPressed {
+                    slot,
+                    button: *button,
+                }
+            }
+            GamepadEvent::ButtonReleased { id, button } => {
+                let slot = self.slot_of(*id)?;
+                self.slots[slot].as_mut()?.pressed.remove(button);
+                InputEventKind::GamepadButtonReleased {
+                    slot,
+                    button: *button,
+                }
+            }
+            GamepadEvent::AxisChanged { id, axis, value } => {
+                let slot = self.slot_of(*id)?;
+                let value = value.clamp(-1.0, 1.0);
+                self.slots[slot].as_mut()?.axes[*axis as usize] = value;
+                InputEventKind::GamepadAxisChanged {
+                    slot,
+                    axis: *axis,
+                    value,
+                }
+            }
+        };
+        Some(kind)
+    }
+
+    fn slot_of(&self, id: u64) -> Option<usize> {
+        self.slots
+            .iter()
+            .position(|state| state.as_ref().is_some_and(|state| state.id == id))
+    }
+
+    /// Save current buttons in previous and clear connection changes
+    /// Should be called each frame
+    pub(crate) fn save_buttons(&mut self) {
+        for state in self.slots.iter_mut().flatten() {
+            state.previous_pressed = state.pressed.clone();
+        }
+        self.just_connected.clear();
+        self.just_disconnected.clear();
+    }
+}
+
+/// Rescales value so the dead zone maps to 0 and the rest to [0,1]
+fn apply_dead_zone(value: f32, dead_zone: f32) -> f32 {
+    if value.abs() <= dead_zone {
+        return 0.0;
+    }
+    value.signum() * (value.abs() - dead_zone) / (1.0 - dead_zone)
+}
+
+/// Gamepad backend using gilrs
+#[cfg(feature = "gilrs")]
+pub struct GilrsBackend {
+    gilrs: gilrs::Gilrs,
+}
+
+#[cfg(feature = "gilrs")]
+impl GilrsBackend {
+    pub fn new() -> Result<Self, Box<gilrs::Error>> {
+        Ok(Self {
+            gilrs: gilrs::Gilrs::new().map_err(Box::new)?,
+        })
+    }
+}
+
+#[cfg(feature = "gilrs")]
+impl GamepadBackend for GilrsBackend {
+    fn poll(&mut self, events: &mut Vec<(Instant, GamepadEvent)>) {
+        use gilrs::{Axis, Button, EventType};
+        use std::time::SystemTime;
+
+        // gilrs stamps events with the system clock
+        let (now, system_now) = (Instant::now(), SystemTime::now());
+        let to_instant = |time: SystemTime| {
+            let age = system_now.duration_since(time).unwrap_or_default();
+            now.checked_sub(age).unwrap_or(now)
+        };
+
+        let map_button = |button: Button| {
+            Some(match button {
+                Button::South => GamepadButton::South,
+                Button::East => GamepadButton::East,
+                Button::North => GamepadButton::North,
+                Button::West => GamepadButton::West,
+                Button::LeftTrigger => GamepadButton::LeftBumper,
+                Button::RightTrigger => GamepadButton::RightBumper,
+                Button::LeftThumb => GamepadButton::LeftStick,
+                Button::RightThumb => GamepadButton::RightStick,
+                Button::Select => GamepadButton::Select,
+                Button::Start => GamepadButton::Start,
+                Button::Mode => GamepadButton::Mode,
+                Button::DPadUp => GamepadButton::DPadUp,
+                Button::DPadDown => GamepadButton::DPadDown,
+                Button::DPadLeft => GamepadButton::DPadLeft,
+                Button::DPadRight => GamepadButton::DPadRight,
+                _ => return None,
+            })
+        };
+
+        while let Some(gilrs::Event { id, event, time }) = self.gilrs.next_event() {
+            let gamepad = id;
+            let id = usize::from(id) as u64;
+            let event = match event {
+                EventType::Connected => GamepadEvent::Connected {
+                    id,
+                    name: self.gilrs.gamepad(gamepad).name().to_string(),
+                },
+                EventType::Disconnected => GamepadEvent::Disconnected { id },
+                EventType::ButtonPressed(button, _) => match map_button(button) {
+                    Some(button) => GamepadEvent::ButtonPressed { id, button },
+                    None => continue,
+                },
+                EventType::ButtonReleased(button, _) => match map_button(button) {
+                    Some(button) => GamepadEvent::ButtonReleased { id, button },
+                    None => continue,
+                },
+                // Analog triggers are reported as buttons
+                EventType::ButtonChanged(Button::LeftTrigger2, value, _) => {
+                    GamepadEvent::AxisChanged {
+                        id,
+                        axis: GamepadAxis::LeftTrigger,
+                        value,
+                    }
+                }
+                EventType::ButtonChanged(Button::RightTrigger2, value, _) => {
+                    GamepadEvent::AxisChanged {
+                        id,
+                        axis: GamepadAxis::RightTrigger,
+                        value,
+                    }
+                }
+                EventType::AxisChanged(axis, value, _) => {
+                    let axis = match axis {
+                        Axis::LeftStickX => GamepadAxis::LeftStickX,
+                        Axis::LeftStickY => GamepadAxis::LeftStickY,
+                        Axis::RightStickX => GamepadAxis::RightStickX,
+                        Axis::RightStickY => GamepadAxis::RightStickY,
+                        _ => continue,
+                    };
+                    GamepadEvent::AxisChanged { id, axis, value }
+                }
+                _ => continue,
+            };
+            events.push((to_instant(time), event));
+        }
+    }
+}
+
+//
+// Gamepad commands
+//
+
+/// If button is pressed on the gamepad in slot
+///
+/// Slots are assigned in connection order, reusing the lowest free slot
+pub fn gamepad_button_pressed(ctx: &Context, slot: usize, button: GamepadButton) -> bool {
+    ctx.input.gamepad.button_pressed(slot, button)
+}
+
+/// If button was pressed this frame on the gamepad in slot
+pub fn gamepad_button_just_pressed(ctx: &Context, slot: usize, button: GamepadButton) -> bool {
+    ctx.input.gamepad.button_just_pressed(slot, button)
+}
+
+/// If button was released this frame on the gamepad in slot
+pub fn gamepad_button_released(ctx: &Context, slot: usize, button: GamepadButton) -> bool {
+    ctx.input.gamepad.button_released(slot, button)
+}
+
+/// Value of an axis on the gamepad in slot with dead zones applied
+///
+/// 0.0 if no gamepad is connected in slot
+pub fn gamepad_axis(ctx: &Context, slot: usize, axis: GamepadAxis) -> f32 {
+    ctx.input.gamepad.axis(slot, axis)
+}
+
+/// Left stick (x, y) on the gamepad in slot with dead zone applied
+pub fn gamepad_left_stick(ctx: &Context, slot: usize) -> (f32, f32) {
+    (
+        ctx.input.gamepad.axis(slot, GamepadAxis::LeftStickX),
+        ctx.input.gamepad.axis(slot, GamepadAxis::LeftStickY),
+    )
+}
+
+/// Right stick (x, y) on the gamepad in slot with dead zone applied
+pub fn gamepad_right_stick(ctx: &Context, slot: usize) -> (f32, f32) {
+    (
+        ctx.input.gamepad.axis(slot, GamepadAxis::RightStickX),
+        ctx.input.gamepad.axis(slot, GamepadAxis::RightStickY),
+    )
+}
+
+/// If a gamepad is connected in slot
+pub fn gamepad_connected(ctx: &Context, slot: usize) -> bool {
+    ctx.input.gamepad.state(slot).is_some()
+}
+
+/// Slots of all connected gamepads
+pub fn gamepads(ctx: &Context) -> impl Iterator<Item = usize> + '_ {
+    ctx.input
+        .gamepad
+        .slots
+        .iter()
+        .enumerate()
+        .filter_map(|(slot, state)| state.as_ref().map(|_| slot))
+}
+
+/// Name of the gamepad in slot
+pub fn gamepad_name(ctx: &Context, slot: usize) -> Option<&str> {
+    ctx.input
+        .gamepad
+        .state(slot)
+        .map(|state| state.name.as_str())
+}
+
+/// Slots of gamepads connected this frame
+pub fn gamepads_connected(ctx: &Context) -> &[usize] {
+    &ctx.input.gamepad.just_connected
+}
+
+/// Slots of gamepads disconnected this frame
+pub fn gamepads_disconnected(ctx: &Context) -> &[usize] {
+    &ctx.input.gamepad.just_disconnected
+}
+
+/// Sets the radial dead zone of both sticks [0,1)
+pub fn set_gamepad_stick_dead_zone(ctx: &mut Context, dead_zone: f32) {
+    ctx.input.gamepad.stick_dead_zone = dead_zone.clamp(0.0, 0.99);
+}
+
+/// Sets the dead zone of both triggers [0,1)
+pub fn set_gamepad_trigger_dead_zone(ctx: &mut Context, dead_zone: f32) {
+    ctx.input.gamepad.trigger_dead_zone = dead_zone.clamp(0.0, 0.99);
+}
+
+/// Replaces the source of gamepad events
+///
+/// Connected gamepads stay until the new backend disconnects them
+pub fn set_gamepad_backend(ctx: &mut Context, backend: impl GamepadBackend + 'static) {
+    ctx.input.gamepad.backend = Some(Box::new(backend));
+}
+
 ///
 /// Tests
 ///
 
 #[cfg(test)]
+#[allow(clippy::empty_line_after_doc_comments)]
 mod tests {
     use winit::event::Modifiers;
     use winit::keyboard::ModifiersState;
 
+    use crate::input::GamepadAxis;
+    use crate::input::GamepadBackend;
+    use crate::input::GamepadButton;
+    use crate::input::GamepadContext;
+    use crate::input::GamepadEvent;
     use crate::input::KeyCode;
     use crate::input::KeyModifier;
     use crate::input::KeyboardContext;
+    use crate::input::{
+        clipboard_text, copy_text, edit_text, edit_text_at, is_shortcut, text_input, Key, NamedKey,
+        TextContext, TextEdit,
+    };
+    use crate::input::{events, InputContext, InputEventKind};
+    use crate::input::{key_just_pressed, key_pressed, key_released, mouse_button_just_pressed};
+    use crate::input::{key_repeated, set_key_repeat, shortcut_just_pressed};
+    use crate::input::{
+        mouse_button_pressed, mouse_pos_pixel, set_touch_mouse_emulation, touch, touch_count,
+        touches, Touch, TouchPhase,
+    };
+    use crate::input::{
+        mouse_double_clicked, mouse_drag, mouse_drag_delta, mouse_drag_ended, mouse_drag_started,
+        mouse_long_pressed, set_gesture_thresholds, update_gestures, Drag, GestureThresholds,
+        MouseButton,
+    };
+    use crate::time::{advance_clock, set_clock, Clock};
+    use crate::Context;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::time::{Duration, Instant};
+    use winit::keyboard::PhysicalKey;
 
     #[test]
     fn key_pressed_test() {
@@ -410,4 +1951,613 @@ mod tests {
         assert!(kc.modifier_released(KeyModifier::Shift));
         assert!(!kc.modifier_released(KeyModifier::Ctrl));
     }
+
+    /// Backend replaying events pushed from the test
+    struct MockBackend(Rc<RefCell<Vec<GamepadEvent>>>);
+
+    impl GamepadBackend for MockBackend {
+        fn poll(&mut self, events: &mut Vec<(Instant, GamepadEvent)>) {
+            let now = Instant::now();
+            events.extend(self.0.borrow_mut().drain(..).map(|event| (now, event)));
+        }
+    }
+
+    fn mock_gamepads() -> (GamepadContext, Rc<RefCell<Vec<GamepadEvent>>>) {
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let gc = GamepadContext {
+            backend: Some(Box::new(MockBackend(events.clone()))),
+            ..Default::default()
+        };
+        (gc, events)
+    }
+
+    #[test]
+    fn gamepad_button_test() {
+        let (mut gc, events) = mock_gamepads();
+        events.borrow_mut().extend([
+            GamepadEvent::Connected {
+                id: 7,
+                name: String::from("pad"),
+            },
+            GamepadEvent::ButtonPressed {
+                id: 7,
+                button: GamepadButton::South,
+            },
+        ]);
+        gc.poll();
+
+        assert_eq!(gc.just_connected, [0]);
+        assert!(gc.button_pressed(0, GamepadButton::South));
+        assert!(gc.button_just_pressed(0, GamepadButton::South));
+        assert!(!gc.button_pressed(1, GamepadButton::South));
+
+        gc.save_buttons();
+        events.borrow_mut().push(GamepadEvent::ButtonReleased {
+            id: 7,
+            button: GamepadButton::South,
+        });
+        gc.poll();
+
+        assert!(gc.just_connected.is_empty());
+        assert!(!gc.button_pressed(0, GamepadButton::South));
+        assert!(gc.button_released(0, GamepadButton::South));
+    }
+
+    #[test]
+    fn gamepad_slots_test() {
+        let (mut gc, events) = mock_gamepads();
+        let connect = |id| GamepadEvent::Connected {
+            id,
+            name: String::new(),
+        };
+        events.borrow_mut().extend([connect(1), connect(2)]);
+        gc.poll();
+        gc.save_buttons();
+
+        events
+            .borrow_mut()
+            .extend([GamepadEvent::Disconnected { id: 1 }, connect(3)]);
+        gc.poll();
+
+        // Gamepad 3 takes the slot freed by gamepad 1
+        assert_eq!(gc.just_disconnected, [0]);
+        assert_eq!(gc.just_connected, [0]);
+        assert_eq!(gc.state(0).unwrap().id, 3);
+        assert_eq!(gc.state(1).unwrap().id, 2);
+    }
+
+    #[test]
+    fn gamepad_dead_zone_test() {
+        let (mut gc, _) = mock_gamepads();
+        gc.handle_event(&GamepadEvent::Connected {
+            id: 0,
+            name: String::new(),
+        });
+        let mut set = |axis, value| {
+            gc.handle_event(&GamepadEvent::AxisChanged { id: 0, axis, value });
+        };
+        set(GamepadAxis::LeftStickX, 0.1);
+        set(GamepadAxis::LeftStickY, 0.1);
+        set(GamepadAxis::RightStickX, 1.0);
+        set(GamepadAxis::LeftTrigger, 0.525);
+
+        // Inside the radial dead zone
+        assert_eq!(gc.axis(0, GamepadAxis::LeftStickX), 0.0);
+        assert_eq!(gc.axis(0, GamepadAxis::LeftStickY), 0.0);
+
+        assert_eq!(gc.axis(0, GamepadAxis::RightStickX), 1.0);
+        assert!((gc.axis(0, GamepadAxis::LeftTrigger) - 0.5).abs() < 1e-6);
+        assert_eq!(gc.axis(1, GamepadAxis::RightStickX), 0.0);
+    }
+
+    #[test]
+    fn text_input_test() {
+        let mut tc = TextContext::default();
+        tc.insert("h");
+        tc.insert("é\r");
+        tc.edit(TextEdit::Backspace);
+        tc.insert("y");
+
+        assert_eq!(tc.text, "héy");
+        assert_eq!(
+            tc.edits,
+            [
+                TextEdit::Insert(String::from("hé")),
+                TextEdit::Backspace,
+                TextEdit::Insert(String::from("y")),
+            ]
+        );
+
+        tc.clear();
+        assert!(tc.text.is_empty());
+        assert!(tc.edits.is_empty());
+    }
+
+    #[test]
+    fn copy_paste_test() {
+        let mut ctx = Context::headless(8, 8);
+        copy_text(&mut ctx, "hi\tthere\n");
+        assert_eq!(clipboard_text(&mut ctx), "hi\tthere\n");
+
+        // Control characters such as tab and newline are not typed
+        ctx.input.paste();
+        assert_eq!(text_input(&ctx), "hithere");
+        let mut text = String::from(">");
+        assert!(!edit_text(&ctx, &mut text));
+        assert_eq!(text, ">hithere");
+    }
+
+    #[test]
+    fn altgr_is_not_shortcut_test() {
+        // AltGr is reported as Ctrl+Alt on Windows
+        assert!(!is_shortcut(true, true));
+        assert!(!is_shortcut(false, true));
+        assert!(!is_shortcut(false, false));
+        assert_eq!(is_shortcut(true, false), !cfg!(target_os = "macos"));
+    }
+
+    #[test]
+    fn edit_text_test() {
+        let mut ctx = Context::headless(8, 8);
+        ctx.input.text.insert("ab");
+        ctx.input.text.edit(TextEdit::Backspace);
+        ctx.input.text.insert("c");
+        ctx.input.text.edit(TextEdit::Submit);
+
+        let mut text = String::from("x");
+        assert!(edit_text(&ctx, &mut text));
+        assert_eq!(text, "xac");
+        assert_eq!(text_input(&ctx), "abc");
+    }
+
+    #[test]
+    fn edit_text_at_test() {
+        let mut ctx = Context::headless(8, 8);
+        ctx.input.text.edit(TextEdit::Delete);
+        ctx.input.text.insert("é");
+        ctx.input.text.edit(TextEdit::Backspace);
+        ctx.input.text.edit(TextEdit::Backspace);
+        ctx.input.text.insert("x");
+
+        let mut text = String::from("abcd");
+        let mut cursor = 2;
+        assert!(!edit_text_at(&ctx, &mut text, &mut cursor));
+        assert_eq!(text, "axd");
+        assert_eq!(cursor, 2);
+
+        // Delete at the end of the text does nothing
+        assert!(!TextEdit::Delete.apply(&mut text));
+        assert_eq!(text, "axd");
+    }
+
+    #[test]
+    fn logical_key_test() {
+        let mut kc = KeyboardContext::default();
+        let z = Key::Character("z".into());
+
+        // KeyW produces z on AZERTY, uppercase with shift held
+        kc.press(PhysicalKey::Code(KeyCode::KeyW), Key::Character("Z".into()));
+
+        assert!(kc.key_pressed(KeyCode::KeyW));
+        assert!(kc.logical_key_pressed(&z));
+        assert!(kc.logical_key_just_pressed(&z));
+
+        kc.save_keys();
+        // Released after shift, reporting a different logical key
+        kc.release(PhysicalKey::Code(KeyCode::KeyW));
+
+        assert!(!kc.key_pressed(KeyCode::KeyW));
+        assert!(!kc.logical_key_pressed(&z));
+        assert!(kc.logical_key_released(&z));
+    }
+
+    #[test]
+    fn logical_key_held_by_two_keys_test() {
+        let mut kc = KeyboardContext::default();
+        let enter = Key::Named(NamedKey::Enter);
+        kc.press(PhysicalKey::Code(KeyCode::Enter), enter.clone());
+        kc.press(PhysicalKey::Code(KeyCode::NumpadEnter), enter.clone());
+        kc.release(PhysicalKey::Code(KeyCode::Enter));
+
+        assert!(kc.logical_key_pressed(&enter));
+    }
+
+    #[test]
+    fn events_test() {
+        let mut ctx = Context::headless(8, 8);
+        let a = Key::Character("a".into());
+        ctx.input
+            .press_key(PhysicalKey::Code(KeyCode::KeyA), a.clone(), false);
+        ctx.input.type_text("a");
+        ctx.input
+            .release_key(PhysicalKey::Code(KeyCode::KeyA), a.clone());
+
+        // Pressed and released within the frame
+        assert!(!ctx.input.keyboard.key_pressed(KeyCode::KeyA));
+        let kinds = events(&ctx).map(|event| &event.kind).collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                &InputEventKind::KeyPressed {
+                    key: Some(KeyCode::KeyA),
+                    logical: a.clone(),
+                    repeat: false
+                },
+                &InputEventKind::Text(String::from("a")),
+                &InputEventKind::KeyReleased {
+                    key: Some(KeyCode::KeyA),
+                    logical: a
+                },
+            ]
+        );
+        assert!(events(&ctx)
+            .zip(events(&ctx).skip(1))
+            .all(|(a, b)| a.time <= b.time));
+    }
+
+    #[test]
+    fn gamepad_events_test() {
+        let (gc, events) = mock_gamepads();
+        let mut ic = InputContext {
+            gamepad: gc,
+            ..Default::default()
+        };
+        events.borrow_mut().extend([
+            GamepadEvent::ButtonPressed {
+                id: 3,
+                button: GamepadButton::Start,
+            },
+            GamepadEvent::Connected {
+                id: 3,
+                name: String::new(),
+            },
+            GamepadEvent::ButtonPressed {
+                id: 3,
+                button: GamepadButton::Start,
+            },
+        ]);
+        ic.poll_gamepads();
+
+        // Events of unknown gamepads are dropped
+        let kinds = ic
+            .events
+            .iter()
+            .map(|event| &event.kind)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                &InputEventKind::GamepadConnected { slot: 0 },
+                &InputEventKind::GamepadButtonPressed {
+                    slot: 0,
+                    button: GamepadButton::Start
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn gamepad_events_ordered_by_time_test() {
+        /// Backend replaying events with fixed times
+        struct TimedBackend(Vec<(Instant, GamepadEvent)>);
+
+        impl GamepadBackend for TimedBackend {
+            fn poll(&mut self, events: &mut Vec<(Instant, GamepadEvent)>) {
+                events.append(&mut self.0);
+            }
+        }
+
+        let earlier = Instant::now() - Duration::from_millis(1);
+        let mut ic = InputContext::default();
+        ic.record(InputEventKind::MouseButtonPressed(MouseButton::Left));
+        ic.gamepad.backend = Some(Box::new(TimedBackend(vec![(
+            earlier,
+            GamepadEvent::Connected {
+                id: 0,
+                name: String::new(),
+            },
+        )])));
+        ic.poll_gamepads();
+
+        // The gamepad connected before the click although it was polled after
+        let kinds = ic
+            .events
+            .iter()
+            .map(|event| &event.kind)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                &InputEventKind::GamepadConnected { slot: 0 },
+                &InputEventKind::MouseButtonPressed(MouseButton::Left),
+            ]
+        );
+    }
+
+    /// Run the gesture step of a frame with mouse events at canvas pixels
+    fn gesture_frame(ctx: &mut Context, seconds: f32, events: &[InputEventKind]) {
+        advance_clock(ctx, Duration::from_secs_f32(seconds));
+        ctx.time.update_time();
+        for kind in events {
+            ctx.input.record(kind.clone());
+        }
+        update_gestures(ctx);
+        ctx.input.events.clear();
+    }
+
+    #[test]
+    fn double_click_test() {
+        let mut ctx = Context::headless(64, 64);
+        set_clock(&mut ctx, Clock::Manual);
+        let left = MouseButton::Left;
+        let click = [
+            InputEventKind::MouseMoved { x: 10.0, y: 10.0 },
+            InputEventKind::MouseButtonPressed(left),
+            InputEventKind::MouseButtonReleased(left),
+        ];
+
+        gesture_frame(&mut ctx, 0.0, &click);
+        assert!(!mouse_double_clicked(&ctx, left));
+        gesture_frame(&mut ctx, 0.1, &click);
+        assert!(mouse_double_clicked(&ctx, left));
+
+        // A third click starts a new double click
+        gesture_frame(&mut ctx, 0.1, &click);
+        assert!(!mouse_double_clicked(&ctx, left));
+
+        // Too slow
+        gesture_frame(&mut ctx, 1.0, &click);
+        assert!(!mouse_double_clicked(&ctx, left));
+    }
+
+    #[test]
+    fn drag_test() {
+        let mut ctx = Context::headless(64, 64);
+        set_clock(&mut ctx, Clock::Manual);
+        let left = MouseButton::Left;
+
+        gesture_frame(
+            &mut ctx,
+            0.0,
+            &[
+                InputEventKind::MouseMoved { x: 10.0, y: 10.0 },
+                InputEventKind::MouseButtonPressed(left),
+                InputEventKind::MouseMoved { x: 11.0, y: 10.0 },
+            ],
+        );
+        assert!(!mouse_drag_started(&ctx, left));
+        assert_eq!(mouse_drag(&ctx, left), None);
+
+        gesture_frame(
+            &mut ctx,
+            0.1,
+            &[InputEventKind::MouseMoved { x: 14.0, y: 12.0 }],
+        );
+        assert!(mouse_drag_started(&ctx, left));
+        assert_eq!(mouse_drag_delta(&ctx, left), (4, 2));
+
+        gesture_frame(
+            &mut ctx,
+            0.1,
+            &[InputEventKind::MouseMoved { x: 12.0, y: 12.0 }],
+        );
+        assert!(!mouse_drag_started(&ctx, left));
+        assert_eq!(mouse_drag_delta(&ctx, left), (-2, 0));
+        assert_eq!(
+            mouse_drag(&ctx, left),
+            Some(Drag {
+                start: (10, 10),
+                end: (12, 12)
+            })
+        );
+
+        // Dragging is not a long press
+        gesture_frame(&mut ctx, 1.0, &[InputEventKind::MouseButtonReleased(left)]);
+        assert!(!mouse_long_pressed(&ctx, left));
+        assert_eq!(
+            mouse_drag_ended(&ctx, left),
+            Some(Drag {
+                start: (10, 10),
+                end: (12, 12)
+            })
+        );
+        assert_eq!(mouse_drag(&ctx, left), None);
+    }
+
+    #[test]
+    fn long_press_test() {
+        let mut ctx = Context::headless(64, 64);
+        set_clock(&mut ctx, Clock::Manual);
+        let right = MouseButton::Right;
+        set_gesture_thresholds(
+            &mut ctx,
+            GestureThresholds {
+                long_press_time: 1.0,
+                ..Default::default()
+            },
+        );
+
+        gesture_frame(&mut ctx, 0.0, &[InputEventKind::MouseButtonPressed(right)]);
+        gesture_frame(&mut ctx, 0.5, &[]);
+        assert!(!mouse_long_pressed(&ctx, right));
+        gesture_frame(&mut ctx, 0.5, &[]);
+        assert!(mouse_long_pressed(&ctx, right));
+
+        // Fires once per press
+        gesture_frame(&mut ctx, 0.5, &[]);
+        assert!(!mouse_long_pressed(&ctx, right));
+    }
+
+    #[test]
+    fn touch_test() {
+        let mut ctx = Context::headless(64, 64);
+        ctx.input
+            .touch(1, TouchPhase::Started, (4.0, 4.0), Some(0.5));
+        ctx.input.touch(2, TouchPhase::Started, (8.0, 8.0), None);
+        ctx.input.touch(1, TouchPhase::Moved, (6.0, 5.0), Some(0.7));
+
+        assert_eq!(touch_count(&ctx), 2);
+        assert_eq!(
+            touch(&ctx, 1),
+            Some(Touch {
+                id: 1,
+                phase: TouchPhase::Started,
+                pos: (6, 5),
+                start: (4, 4),
+                force: Some(0.7),
+            })
+        );
+        // Touches do not move the mouse unless emulated
+        assert!(!mouse_button_pressed(&ctx, MouseButton::Left));
+
+        ctx.input.touch.save_touches();
+        ctx.input.touch(2, TouchPhase::Ended, (8.0, 9.0), None);
+        assert_eq!(touch(&ctx, 1).unwrap().phase, TouchPhase::Moved);
+        assert_eq!(touch(&ctx, 2).unwrap().phase, TouchPhase::Ended);
+        assert_eq!(touch_count(&ctx), 1);
+
+        ctx.input.touch.save_touches();
+        assert_eq!(touches(&ctx).map(|touch| touch.id).collect::<Vec<_>>(), [1]);
+    }
+
+    #[test]
+    fn touch_tap_within_frame_test() {
+        let mut ctx = Context::headless(64, 64);
+        ctx.input.touch(1, TouchPhase::Started, (4.0, 4.0), None);
+        ctx.input.touch(1, TouchPhase::Ended, (5.0, 4.0), None);
+        assert_eq!(touch(&ctx, 1).unwrap().phase, TouchPhase::Started);
+        assert_eq!(touch(&ctx, 1).unwrap().pos, (5, 4));
+
+        ctx.input.touch.save_touches();
+        assert_eq!(touch(&ctx, 1).unwrap().phase, TouchPhase::Ended);
+        assert_eq!(touch_count(&ctx), 0);
+
+        ctx.input.touch.save_touches();
+        assert_eq!(touch(&ctx, 1), None);
+    }
+
+    #[test]
+    fn touch_mouse_emulation_test() {
+        let mut ctx = Context::headless(64, 64);
+        set_touch_mouse_emulation(&mut ctx, true);
+
+        ctx.input.touch(1, TouchPhase::Started, (4.0, 4.0), None);
+        ctx.input.touch(2, TouchPhase::Started, (8.0, 8.0), None);
+        assert!(mouse_button_pressed(&ctx, MouseButton::Left));
+        assert_eq!(mouse_pos_pixel(&ctx), (4, 4));
+
+        // Only the first touch moves the mouse
+        ctx.input.touch(2, TouchPhase::Moved, (9.0, 9.0), None);
+        ctx.input.touch(1, TouchPhase::Moved, (5.0, 6.0), None);
+        assert_eq!(mouse_pos_pixel(&ctx), (5, 6));
+
+        ctx.input.touch(1, TouchPhase::Ended, (5.0, 6.0), None);
+        assert!(!mouse_button_pressed(&ctx, MouseButton::Left));
+        assert!(events(&ctx)
+            .any(|event| event.kind == InputEventKind::MouseButtonReleased(MouseButton::Left)));
+    }
+
+    #[test]
+    fn pixel_clamped_to_canvas_test() {
+        let mut ctx = Context::headless(64, 32);
+        ctx.input.mouse.set_pos(100.0, -5.0);
+        assert_eq!(mouse_pos_pixel(&ctx), (63, 0));
+
+        ctx.input.touch(1, TouchPhase::Started, (10.0, 10.0), None);
+        ctx.input.touch(1, TouchPhase::Moved, (-3.0, 40.0), None);
+        let touch = touches(&ctx).next().unwrap();
+        assert_eq!((touch.start, touch.pos), ((10, 10), (0, 31)));
+    }
+
+    #[test]
+    fn key_repeated_test() {
+        let mut ctx = Context::headless(8, 8);
+        set_key_repeat(&mut ctx, 0.5, 0.25);
+        let frame = |ctx: &mut Context, delta_time: f32| {
+            ctx.input.keyboard.update_repeat(delta_time);
+            key_repeated(ctx, KeyCode::ArrowDown)
+        };
+
+        ctx.input.keyboard.set_key(KeyCode::ArrowDown);
+        assert!(frame(&mut ctx, 0.125));
+        assert!(!frame(&mut ctx, 0.25));
+        assert!(frame(&mut ctx, 0.25));
+        assert!(!frame(&mut ctx, 0.125));
+        assert!(frame(&mut ctx, 0.125));
+
+        // Releasing resets the delay
+        ctx.input.keyboard.release_key(KeyCode::ArrowDown);
+        assert!(!frame(&mut ctx, 0.125));
+        ctx.input.keyboard.set_key(KeyCode::ArrowDown);
+        assert!(frame(&mut ctx, 0.125));
+        assert!(!frame(&mut ctx, 0.25));
+    }
+
+    #[test]
+    fn shortcut_just_pressed_test() {
+        let mut ctx = Context::headless(8, 8);
+        let (ctrl, shift) = (KeyModifier::Ctrl, KeyModifier::Shift);
+        ctx.input
+            .keyboard
+            .modifiers_changed(&Modifiers::from(ModifiersState::CONTROL));
+        ctx.input.keyboard.set_key(KeyCode::KeyS);
+
+        assert!(shortcut_just_pressed(&ctx, &[ctrl], KeyCode::KeyS));
+        assert!(!shortcut_just_pressed(&ctx, &[ctrl, shift], KeyCode::KeyS));
+        assert!(!shortcut_just_pressed(&ctx, &[], KeyCode::KeyS));
+
+        ctx.input.keyboard.modifiers_changed(&Modifiers::from(
+            ModifiersState::CONTROL | ModifiersState::SHIFT,
+        ));
+        assert!(shortcut_just_pressed(&ctx, &[shift, ctrl], KeyCode::KeyS));
+
+        ctx.input.keyboard.save_keys();
+        assert!(!shortcut_just_pressed(&ctx, &[shift, ctrl], KeyCode::KeyS));
+    }
+
+    #[test]
+    fn latch_test() {
+        let mut ctx = Context::headless(8, 8);
+        ctx.input.keyboard.set_key(KeyCode::Space);
+        ctx.input.mouse.press_button(MouseButton::Left);
+
+        let latched = ctx.input.latch();
+        assert!(!key_just_pressed(&ctx, KeyCode::Space));
+        assert!(!mouse_button_just_pressed(&ctx, MouseButton::Left));
+        assert!(key_pressed(&ctx, KeyCode::Space));
+
+        ctx.input.unlatch(latched);
+        assert!(key_just_pressed(&ctx, KeyCode::Space));
+        assert!(mouse_button_just_pressed(&ctx, MouseButton::Left));
+    }
+
+    #[test]
+    fn fixed_update_edges_test() {
+        let mut ctx = Context::headless(8, 8);
+        let frame_previous = ctx.input.begin_fixed_update();
+        ctx.input.end_fixed_update(frame_previous);
+
+        // Pressed on a frame without a fixed update
+        ctx.input.keyboard.set_key(KeyCode::Space);
+        ctx.input.keyboard.save_keys();
+        assert!(!key_just_pressed(&ctx, KeyCode::Space));
+
+        let frame_previous = ctx.input.begin_fixed_update();
+        assert!(key_just_pressed(&ctx, KeyCode::Space));
+        ctx.input.latch();
+        assert!(!key_just_pressed(&ctx, KeyCode::Space));
+        ctx.input.end_fixed_update(frame_previous);
+        assert!(!key_just_pressed(&ctx, KeyCode::Space));
+
+        // Released on a frame without a fixed update
+        ctx.input.keyboard.release_key(KeyCode::Space);
+        ctx.input.keyboard.save_keys();
+        let frame_previous = ctx.input.begin_fixed_update();
+        assert!(key_released(&ctx, KeyCode::Space));
+        ctx.input.end_fixed_update(frame_previous);
+
+        let frame_previous = ctx.input.begin_fixed_update();
+        assert!(!key_released(&ctx, KeyCode::Space));
+        ctx.input.end_fixed_update(frame_previous);
+    }
 }
@@ -0,0 +1,235 @@
+// Named canvases drawn over the main canvas when presenting
+//
+// Layers are composed on the CPU into a copy of the canvas, which is then uploaded
+
+use crate::{
+    canvas::{BlendMode, Canvas},
+    Context,
+};
+
+pub(crate) struct Layer {
+    name: String,
+    canvas: Canvas,
+    pub(crate) visible: bool,
+    opacity: f32,
+    blend: BlendMode,
+    offset: (i32, i32),
+}
+
+/// If any layer has to be drawn over the canvas
+pub(crate) fn any_visible(layers: &[Layer]) -> bool {
+    layers.iter().any(|layer| layer.visible)
+}
+
+/// Draw the visible layers onto frame, a copy of the canvas, bottom first
+pub(crate) fn compose(layers: &[Layer], frame: &mut Canvas) {
+    // Clipping and masking only apply to user drawing
+    frame.reset_clip();
+    frame.set_mask(None);
+    for layer in layers.iter().filter(|layer| layer.visible) {
+        let (x, y) = layer.offset;
+        frame.blit_with_opacity(&layer.canvas, x, y, layer.blend, layer.opacity);
+    }
+}
+
+fn find<'a>(ctx: &'a Context, name: &str) -> Option<&'a Layer> {
+    ctx.render.layers.iter().find(|layer| layer.name == name)
+}
+
+fn find_mut<'a>(ctx: &'a mut Context, name: &str) -> Option<&'a mut Layer> {
+    ctx.render
+        .layers
+        .iter_mut()
+        .find(|layer| layer.name == name)
+}
+
+//
+// Commands
+//
+
+/// Adds a layer on top of the others, drawn over the canvas when presenting
+///
+/// The layer is visible, fully opaque, alpha blended and placed at the top left corner.
+/// If the name is taken only that layer's canvas is replaced
+pub fn add_layer(ctx: &mut Context, name: &str, canvas: Canvas) {
+    match find_mut(ctx, name) {
+        Some(layer) => layer.canvas = canvas,
+        None => ctx.render.layers.push(Layer {
+            name: name.to_string(),
+            canvas,
+            visible: true,
+            opacity: 1.0,
+            blend: BlendMode::Alpha,
+            offset: (0, 0),
+        }),
+    }
+}
+
+/// Removes a layer, returning its canvas
+pub fn remove_layer(ctx: &mut Context, name: &str) -> Option<Canvas> {
+    let index = ctx
+        .render
+        .layers
+        .iter()
+        .position(|layer| layer.name == name)?;
+    Some(ctx.render.layers.remove(index).canvas)
+}
+
+/// Moves a layer to index in the stack, 0 being the bottom
+///
+/// Indices past the top move it to the top
+pub fn move_layer(ctx: &mut Context, name: &str, index: usize) {
+    let layers = &mut ctx.render.layers;
+    if let Some(current) = layers.iter().position(|layer| layer.name == name) {
+        let layer = layers.remove(current);
+        layers.insert(index.min(layers.len()), layer);
+    }
+}
+
+/// Names of all layers, bottom first
+pub fn layer_names(ctx: &Context) -> impl Iterator<Item = &str> {
+    ctx.render.layers.iter().map(|layer| layer.name.as_str())
+}
+
+/// Canvas of a layer
+pub fn layer_ref<'a>(ctx: &'a Context, name: &str) -> Option<&'a Canvas> {
+    find(ctx, name).map(|layer| &layer.canvas)
+}
+
+/// Mutable canvas of a layer to draw into
+pub fn layer_mut<'a>(ctx: &'a mut Context, name: &str) -> Option<&'a mut Canvas> {
+    find_mut(ctx, name).map(|layer| &mut layer.canvas)
+}
+
+/// Shows or hides a layer
+///
+/// Does nothing for unknown layers
+pub fn set_layer_visible(ctx: &mut Context, name: &str, visible: bool) {
+    if let Some(layer) = find_mut(ctx, name) {
+        layer.visible = visible;
+    }
+}
+
+/// Sets the opacity \[0,1\] a layer is drawn with
+///
+/// Does nothing for unknown layers
+pub fn set_layer_opacity(ctx: &mut Context, name: &str, opacity: f32) {
+    if let Some(layer) = find_mut(ctx, name) {
+        layer.opacity = opacity.clamp(0.0, 1.0);
+    }
+}
+
+/// Sets how a layer is combined with what is below it
+///
+/// Does nothing for unknown layers
+pub fn set_layer_blend(ctx: &mut Context, name: &str, blend: BlendMode) {
+    if let Some(layer) = find_mut(ctx, name) {
+        layer.blend = blend;
+    }
+}
+
+/// Sets where the top left corner of a layer is drawn in canvas pixels
+///
+/// Does nothing for unknown layers
+pub fn set_layer_offset(ctx: &mut Context, name: &str, x: i32, y: i32) {
+    if let Some(layer) = find_mut(ctx, name) {
+        layer.offset = (x, y);
+    }
+}
+
+//
+// Tests
+//
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        add_layer, layer_mut, layer_names, move_layer, remove_layer, set_layer_blend,
+        set_layer_offset, set_layer_opacity, set_layer_visible,
+    };
+    use crate::canvas::{BlendMode, Canvas};
+    use crate::Context;
+    use std::borrow::Cow;
+
+    fn solid(width: u32, height: u32, color: &[u8; 3]) -> Canvas {
+        let mut canvas = Canvas::new(width, height);
+        canvas.clear_screen(color);
+        canvas
+    }
+
+    /// Pixel of the presented frame
+    fn presented(ctx: &mut Context, x: u32, y: u32) -> [u8; 3] {
+        ctx.render.finish_frame();
+        ctx.render
+            .overlay
+            .as_ref()
+            .unwrap_or(&ctx.render.canvas)
+            .pixel_rgb(x, y)
+    }
+
+    #[test]
+    fn test_layers_flattened_in_order() {
+        let mut ctx = Context::headless(4, 4);
+        add_layer(&mut ctx, "red", solid(2, 2, &[255, 0, 0]));
+        add_layer(&mut ctx, "blue", Canvas::new(4, 4));
+        layer_mut(&mut ctx, "blue")
+            .unwrap()
+            .write_pixel(0, 0, &[0, 0, 255]);
+        set_layer_offset(&mut ctx, "red", 1, 1);
+
+        assert_eq!(presented(&mut ctx, 0, 0), [0, 0, 255]);
+        assert_eq!(presented(&mut ctx, 1, 1), [255, 0, 0]);
+        assert_eq!(presented(&mut ctx, 3, 3), [0, 0, 0]);
+        // The canvas itself is untouched
+        assert_eq!(ctx.render.canvas.pixel_rgb(1, 1), [0, 0, 0]);
+
+        move_layer(&mut ctx, "blue", 0);
+        add_layer(&mut ctx, "red", solid(4, 4, &[255, 0, 0]));
+        assert_eq!(layer_names(&ctx).collect::<Vec<_>>(), ["blue", "red"]);
+        assert_eq!(presented(&mut ctx, 1, 1), [255, 0, 0]);
+    }
+
+    #[test]
+    fn test_no_layers_presents_canvas() {
+        let mut ctx = Context::headless(2, 2);
+        ctx.render.finish_frame();
+
+        assert!(ctx.render.overlay.is_none());
+        assert!(matches!(ctx.render.frame(), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_layer_visibility_opacity_blend() {
+        let mut ctx = Context::headless(1, 1);
+        ctx.render.canvas.clear_screen(&[100, 100, 100]);
+        add_layer(&mut ctx, "light", solid(1, 1, &[100, 0, 200]));
+
+        set_layer_visible(&mut ctx, "light", false);
+        assert_eq!(presented(&mut ctx, 0, 0), [100, 100, 100]);
+        assert!(ctx.render.overlay.is_none());
+
+        set_layer_visible(&mut ctx, "light", true);
+        set_layer_opacity(&mut ctx, "light", 0.5);
+        assert_eq!(presented(&mut ctx, 0, 0), [100, 50, 150]);
+
+        set_layer_blend(&mut ctx, "light", BlendMode::Add);
+        set_layer_opacity(&mut ctx, "light", 1.0);
+        assert_eq!(presented(&mut ctx, 0, 0), [200, 100, 255]);
+
+        assert!(remove_layer(&mut ctx, "light").is_some());
+        assert_eq!(presented(&mut ctx, 0, 0), [100, 100, 100]);
+    }
+
+    #[test]
+    fn test_export_includes_layers() {
+        let mut ctx = Context::headless(2, 1);
+        add_layer(&mut ctx, "red", solid(1, 1, &[255, 0, 0]));
+        set_layer_offset(&mut ctx, "red", 1, 0);
+
+        let screenshot = crate::debug::screenshot_canvas(&ctx);
+        assert_eq!(screenshot.pixel_rgba(0, 0), [0, 0, 0, 0]);
+        assert_eq!(screenshot.pixel_rgba(1, 0), [255, 0, 0, 255]);
+        // The canvas itself is untouched
+        assert_eq!(ctx.render.canvas.pixel_rgb(1, 0), [0, 0, 0]);
+    }
+}
@@ -1,14 +1,25 @@
+pub mod action;
 pub mod canvas;
+pub mod combo;
+pub mod config;
+pub mod debug;
 pub mod input;
+pub mod layer;
 pub mod media;
 pub mod prelude;
+pub mod profile;
+pub mod terminal;
 pub mod time;
+pub mod tween;
 pub mod window;
 
 mod app;
 mod context;
+mod font;
+mod graphics;
 mod render;
 
 // Re-exports
-pub use app::{run, Callbacks};
+pub use app::{run, App, Callbacks};
+pub use config::AppConfig;
 pub use context::Context;
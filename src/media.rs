@@ -1,7 +1,12 @@
 // Upload screenshots
 
-use crate::Context;
+use crate::{
+    debug,
+    graphics::{encode_kitty, encode_sixel},
+    Context,
+};
 use image::{ImageResult, RgbaImage};
+use std::io::{self, Write};
 
 /// Can take screenshots of a canvas
 pub(crate) struct ScreenshotUploader {
@@ -35,8 +40,27 @@ impl ScreenshotUploader {
 //
 
 /// Export the current canvas to a png at the desired path
+///
+/// Exports include visible layers, but exclude the debug overlay
+/// unless debug::set_overlay_in_screenshots is set
 pub fn export_screenshot(ctx: &Context, path: &str) -> ImageResult<()> {
     ctx.render
         .screenshot_uploader
-        .export_to_file(&ctx.render.canvas.pixels, path)
+        .export_to_file(&debug::screenshot_canvas(ctx).pixels, path)
+}
+
+/// Export the current canvas as a Sixel escape sequence
+///
+/// Writing it to a Sixel capable terminal displays the canvas
+pub fn export_sixel<W: Write>(ctx: &Context, writer: &mut W) -> io::Result<()> {
+    let canvas = debug::screenshot_canvas(ctx);
+    encode_sixel(writer, &canvas.pixels, canvas.width, canvas.height, 1)
+}
+
+/// Export the current canvas as a kitty graphics protocol escape sequence
+///
+/// Writing it to a kitty graphics capable terminal displays the canvas
+pub fn export_kitty<W: Write>(ctx: &Context, writer: &mut W) -> io::Result<()> {
+    let canvas = debug::screenshot_canvas(ctx);
+    encode_kitty(writer, &canvas.pixels, canvas.width, canvas.height, 1, None)
 }
@@ -0,0 +1,254 @@
+// Named profiling scopes and trace export
+
+use crate::{time::TimingStats, Context};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, VecDeque},
+    io::{self, Write},
+    time::{Duration, Instant},
+};
+
+/// Amount of samples kept per scope for stats
+const SCOPE_SAMPLES: usize = 100;
+
+/// Maximum amount of trace events kept for export
+const MAX_TRACE_EVENTS: usize = 100_000;
+
+pub(crate) struct ProfileContext {
+    start_time: Instant,
+    open_scopes: Vec<(String, Instant)>,
+    // engine scopes are named by static strings so recording them does not allocate
+    scopes: BTreeMap<Cow<'static, str>, ScopeSamples>,
+    trace: VecDeque<TraceEvent>,
+}
+
+impl Default for ProfileContext {
+    fn default() -> Self {
+        Self {
+            start_time: Instant::now(),
+            open_scopes: Vec::new(),
+            scopes: BTreeMap::new(),
+            trace: VecDeque::new(),
+        }
+    }
+}
+
+/// Recent durations of a scope in seconds
+struct ScopeSamples {
+    samples: [f32; SCOPE_SAMPLES],
+    index: usize,
+    count: usize,
+    last: f32,
+}
+
+/// Completed scope, timestamps in microseconds since start
+struct TraceEvent {
+    name: Cow<'static, str>,
+    start: u64,
+    duration: u64,
+}
+
+impl ProfileContext {
+    pub(crate) fn begin(&mut self, name: &str) {
+        self.open_scopes.push((name.to_string(), Instant::now()));
+    }
+
+    /// Ends the most recently begun scope
+    pub(crate) fn end(&mut self) {
+        match self.open_scopes.pop() {
+            Some((name, start)) => self.record(name, start, start.elapsed()),
+            None => log::warn!("ended a profiling scope which was never begun"),
+        }
+    }
+
+    /// Record a completed scope
+    ///
+    /// The engine times its scopes with this rather than the scope stack,
+    /// so a user scope without an end does not swallow them
+    pub(crate) fn record(
+        &mut self,
+        name: impl Into<Cow<'static, str>>,
+        start: Instant,
+        duration: Duration,
+    ) {
+        let name = name.into();
+        let seconds = duration.as_secs_f32();
+        if !self.scopes.contains_key(name.as_ref()) {
+            self.scopes.insert(
+                name.clone(),
+                ScopeSamples {
+                    samples: [0.0; SCOPE_SAMPLES],
+                    index: 0,
+                    count: 0,
+                    last: 0.0,
+                },
+            );
+        }
+        let scope = self.scopes.get_mut(name.as_ref()).unwrap();
+        scope.samples[scope.index] = seconds;
+        scope.index = (scope.index + 1) % SCOPE_SAMPLES;
+        scope.count = (scope.count + 1).min(SCOPE_SAMPLES);
+        scope.last = seconds;
+
+        if self.trace.len() == MAX_TRACE_EVENTS {
+            self.trace.pop_front();
+        }
+        self.trace.push_back(TraceEvent {
+            name,
+            start: start.saturating_duration_since(self.start_time).as_micros() as u64,
+            duration: duration.as_micros() as u64,
+        });
+    }
+
+    pub(crate) fn stats(&self, name: &str) -> Option<TimingStats> {
+        self.scopes
+            .get(name)
+            .map(|scope| TimingStats::from_samples(&scope.samples[..scope.count], scope.last))
+    }
+
+    /// Write recorded scopes in the Chrome trace event format
+    pub(crate) fn write_chrome_trace<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        out.write_all(b"{\"traceEvents\":[")?;
+        for (i, event) in self.trace.iter().enumerate() {
+            if i > 0 {
+                out.write_all(b",")?;
+            }
+            out.write_all(b"{\"name\":\"")?;
+            write_json_escaped(out, &event.name)?;
+            write!(
+                out,
+                "\",\"cat\":\"pixelated\",\"ph\":\"X\",\"ts\":{},\"dur\":{},\"pid\":1,\"tid\":1}}",
+                event.start, event.duration
+            )?;
+        }
+        out.write_all(b"]}")
+    }
+}
+
+fn write_json_escaped<W: Write>(out: &mut W, text: &str) -> io::Result<()> {
+    for c in text.chars() {
+        match c {
+            '"' => out.write_all(b"\\\"")?,
+            '\\' => out.write_all(b"\\\\")?,
+            '\n' => out.write_all(b"\\n")?,
+            '\r' => out.write_all(b"\\r")?,
+            '\t' => out.write_all(b"\\t")?,
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32)?,
+            c => write!(out, "{}", c)?,
+        }
+    }
+    Ok(())
+}
+
+//
+// Commands
+//
+
+/// Starts timing a named scope
+///
+/// Scopes nest and are ended in reverse order with [`end_scope`]
+pub fn begin_scope(ctx: &mut Context, name: &str) {
+    ctx.profile.begin(name);
+}
+
+/// Ends the most recently begun scope
+pub fn end_scope(ctx: &mut Context) {
+    ctx.profile.end();
+}
+
+/// Times a closure as a named scope
+pub fn scope<R>(ctx: &mut Context, name: &str, f: impl FnOnce(&mut Context) -> R) -> R {
+    ctx.profile.begin(name);
+    let result = f(ctx);
+    ctx.profile.end();
+    result
+}
+
+/// Durations (seconds) of a scope over its recent samples
+///
+/// The engine records the built-in scopes "fixed_update", "update", "render",
+/// "upload" and "present" each frame
+pub fn scope_stats(ctx: &Context, name: &str) -> Option<TimingStats> {
+    ctx.profile.stats(name)
+}
+
+/// Names of all recorded scopes
+pub fn scope_names(ctx: &Context) -> impl Iterator<Item = &str> {
+    ctx.profile.scopes.keys().map(AsRef::as_ref)
+}
+
+/// Export recorded scopes as Chrome trace event JSON
+///
+/// The output can be opened in chrome://tracing or Perfetto
+pub fn export_chrome_trace<W: Write>(ctx: &Context, writer: &mut W) -> io::Result<()> {
+    ctx.profile.write_chrome_trace(writer)
+}
+
+//
+// Tests
+//
+
+#[cfg(test)]
+mod tests {
+    use super::ProfileContext;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_nested_scopes() {
+        let mut profile = ProfileContext::default();
+        profile.begin("outer");
+        profile.begin("inner");
+        profile.end();
+        profile.end();
+        profile.end();
+
+        assert_eq!(profile.stats("outer").unwrap().samples, 1);
+        assert_eq!(profile.stats("inner").unwrap().samples, 1);
+        assert!(profile.stats("outer").unwrap().last >= profile.stats("inner").unwrap().last);
+        assert!(profile.stats("missing").is_none());
+    }
+
+    #[test]
+    fn test_unended_scope_not_closed_by_engine() {
+        let mut profile = ProfileContext::default();
+        profile.begin("user");
+        profile.record("update", Instant::now(), Duration::from_millis(1));
+
+        assert!(profile.stats("user").is_none());
+        assert_eq!(profile.stats("update").unwrap().samples, 1);
+        profile.end();
+        assert_eq!(profile.stats("user").unwrap().samples, 1);
+    }
+
+    #[test]
+    fn test_scope_samples_wrap() {
+        let mut profile = ProfileContext::default();
+        let start = Instant::now();
+        for i in 0..150 {
+            profile.record("update", start, Duration::from_millis(i));
+        }
+        let stats = profile.stats("update").unwrap();
+
+        assert_eq!(stats.samples, 100);
+        assert_eq!(stats.min, Duration::from_millis(50).as_secs_f32());
+        assert_eq!(stats.last, Duration::from_millis(149).as_secs_f32());
+    }
+
+    #[test]
+    fn test_chrome_trace() {
+        let mut profile = ProfileContext::default();
+        let start = profile.start_time + Duration::from_micros(10);
+        profile.record("a \"b\"", start, Duration::from_micros(5));
+        profile.record("c", start, Duration::from_micros(1));
+
+        let mut out = Vec::new();
+        profile.write_chrome_trace(&mut out).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "{\"traceEvents\":[\
+             {\"name\":\"a \\\"b\\\"\",\"cat\":\"pixelated\",\"ph\":\"X\",\"ts\":10,\"dur\":5,\"pid\":1,\"tid\":1},\
+             {\"name\":\"c\",\"cat\":\"pixelated\",\"ph\":\"X\",\"ts\":10,\"dur\":1,\"pid\":1,\"tid\":1}]}"
+        );
+    }
+}
@@ -1,12 +1,45 @@
 use crate::{
-    canvas::{Canvas, DEFAULT_CANVAS_HEIGHT, DEFAULT_CANVAS_WIDTH},
+    canvas::Canvas,
+    layer::{self, Layer},
     media::ScreenshotUploader,
+    profile::ProfileContext,
+    terminal::TerminalRenderer,
+    window::CursorSprite,
 };
-use std::sync::Arc;
+use std::{borrow::Cow, sync::Arc, time::Instant};
 use wgpu::{util::DeviceExt, Adapter, Device, PresentMode, Surface, SurfaceConfiguration};
 use winit::window::Window;
 
 pub(crate) struct RenderContext {
+    pub(crate) canvas: Canvas,
+
+    /// Copy of the canvas with layers and overlays drawn on, presented instead of the canvas
+    pub(crate) overlay: Option<Canvas>,
+    // if the overlay was copied from the canvas this frame
+    overlay_drawn: bool,
+
+    /// Drawn over the canvas in order, bottom first
+    pub(crate) layers: Vec<Layer>,
+
+    /// Drawn over the canvas at the mouse position, replacing the system cursor
+    pub(crate) cursor: Option<CursorSprite>,
+    /// If the system cursor is shown while there is no cursor sprite
+    pub(crate) cursor_visible: bool,
+
+    pub(crate) screenshot_uploader: ScreenshotUploader,
+
+    pub(crate) backend: RenderBackend,
+    // canvas size the backend and screenshots were last set up for
+    canvas_texture_size: (u32, u32),
+}
+
+/// Where the canvas gets presented
+pub(crate) enum RenderBackend {
+    Gpu(Box<GpuContext>),
+    Terminal(TerminalRenderer),
+}
+
+pub(crate) struct GpuContext {
     #[allow(dead_code)]
     pub(crate) adapter: wgpu::Adapter,
     pub(crate) device: wgpu::Device,
@@ -15,10 +48,6 @@ pub(crate) struct RenderContext {
     pub(crate) surface_config: wgpu::SurfaceConfiguration,
     pub(crate) window: Arc<Window>,
 
-    pub(crate) canvas: Canvas,
-
-    pub(crate) screenshot_uploader: ScreenshotUploader,
-
     pub(crate) vertex_buffer: wgpu::Buffer,
     pub(crate) index_buffer: wgpu::Buffer,
     pub(crate) render_pipeline: wgpu::RenderPipeline,
@@ -29,7 +58,163 @@ pub(crate) struct RenderContext {
 
 impl RenderContext {
     // Creating some of the wgpu types requires async code
-    pub(crate) async fn new(window: Window) -> Self {
+    pub(crate) async fn new(window: Window, canvas_size: (u32, u32), vsync: bool) -> Self {
+        let (width, height) = canvas_size;
+        let present_mode = if vsync {
+            PresentMode::AutoVsync
+        } else {
+            PresentMode::AutoNoVsync
+        };
+        let gpu = GpuContext::new(window, width, height, present_mode).await;
+
+        // Media
+        let canvas = Canvas::new(width, height);
+        let screenshot_uploader = ScreenshotUploader::new(width, height);
+
+        Self {
+            canvas,
+            overlay: None,
+            overlay_drawn: false,
+            layers: Vec::new(),
+            cursor: None,
+            cursor_visible: true,
+            screenshot_uploader,
+            backend: RenderBackend::Gpu(Box::new(gpu)),
+            canvas_texture_size: (width, height),
+        }
+    }
+
+    /// Create a render context which presents to the terminal
+    pub(crate) fn new_terminal(terminal: TerminalRenderer, width: u32, height: u32) -> Self {
+        Self {
+            canvas: Canvas::new(width, height),
+            overlay: None,
+            overlay_drawn: false,
+            layers: Vec::new(),
+            cursor: None,
+            cursor_visible: true,
+            screenshot_uploader: ScreenshotUploader::new(width, height),
+            backend: RenderBackend::Terminal(terminal),
+            canvas_texture_size: (width, height),
+        }
+    }
+
+    /// The window, if presenting to one
+    pub(crate) fn window(&self) -> Option<&Arc<Window>> {
+        match &self.backend {
+            RenderBackend::Gpu(gpu) => Some(&gpu.window),
+            RenderBackend::Terminal(_) => None,
+        }
+    }
+
+    /// Size of the presentation surface in physical pixels
+    ///
+    /// The terminal backend presents the canvas 1:1
+    pub(crate) fn window_size(&self) -> (u32, u32) {
+        match &self.backend {
+            RenderBackend::Gpu(gpu) => {
+                let size = gpu.window.inner_size();
+                (size.width, size.height)
+            }
+            RenderBackend::Terminal(_) => (self.canvas.width, self.canvas.height),
+        }
+    }
+
+    pub(crate) fn resize_canvas_texture(&mut self, width: u32, height: u32) {
+        match &mut self.backend {
+            RenderBackend::Gpu(gpu) => gpu.resize_canvas_texture(width, height),
+            RenderBackend::Terminal(terminal) => terminal.invalidate(),
+        }
+        self.screenshot_uploader.resize(width, height);
+        self.canvas_texture_size = (width, height);
+    }
+
+    /// Follow a canvas resized directly, e.g. through canvas_mut
+    pub(crate) fn sync_canvas_size(&mut self) {
+        let (width, height) = (self.canvas.width, self.canvas.height);
+        if (width, height) != self.canvas_texture_size {
+            self.resize_canvas_texture(width, height);
+        }
+    }
+
+    pub(crate) fn reconfigure_present_mode(&mut self, present_mode: wgpu::PresentMode) {
+        if let RenderBackend::Gpu(gpu) = &mut self.backend {
+            gpu.reconfigure_present_mode(present_mode);
+        }
+    }
+
+    pub(crate) fn resize_window(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
+        if let RenderBackend::Gpu(gpu) = &mut self.backend {
+            gpu.resize_window(new_size);
+        }
+    }
+
+    /// The canvas with the visible layers flattened onto it, without overlays
+    pub(crate) fn frame(&self) -> Cow<'_, Canvas> {
+        if !layer::any_visible(&self.layers) {
+            return Cow::Borrowed(&self.canvas);
+        }
+        let mut frame = self.canvas.clone();
+        layer::compose(&self.layers, &mut frame);
+        Cow::Owned(frame)
+    }
+
+    /// Copy of this frame's composed canvas to draw overlays on
+    ///
+    /// Copied and composed once per frame, on first use
+    pub(crate) fn overlay_canvas(&mut self) -> &mut Canvas {
+        if !self.overlay_drawn {
+            self.overlay = None;
+            self.overlay_drawn = true;
+        }
+        let (canvas, layers) = (&self.canvas, &self.layers);
+        self.overlay.get_or_insert_with(|| {
+            let mut overlay = canvas.clone();
+            layer::compose(layers, &mut overlay);
+            overlay
+        })
+    }
+
+    /// Flatten the layers into the presented frame
+    ///
+    /// Without visible layers or overlays drawn this frame nothing is copied
+    /// and the canvas is presented directly
+    pub(crate) fn finish_frame(&mut self) {
+        if !self.overlay_drawn && layer::any_visible(&self.layers) {
+            self.overlay_canvas();
+        }
+        if !self.overlay_drawn {
+            self.overlay = None;
+        }
+        self.overlay_drawn = false;
+    }
+
+    /// Presents the canvas with its layers and overlays using the current backend
+    ///
+    /// Records the "upload" and "present" profiling scopes
+    pub(crate) fn render(
+        &mut self,
+        profile: &mut ProfileContext,
+    ) -> Result<(), wgpu::SurfaceError> {
+        self.sync_canvas_size();
+        self.finish_frame();
+        let canvas = self.overlay.as_ref().unwrap_or(&self.canvas);
+        match &mut self.backend {
+            RenderBackend::Gpu(gpu) => gpu.render(canvas, profile),
+            RenderBackend::Terminal(terminal) => {
+                let start = Instant::now();
+                if let Err(err) = terminal.present(canvas) {
+                    log::error!("could not present to terminal: {err}");
+                }
+                profile.record("present", start, start.elapsed());
+                Ok(())
+            }
+        }
+    }
+}
+
+impl GpuContext {
+    async fn new(window: Window, width: u32, height: u32, present_mode: PresentMode) -> Self {
         // Create surface
         let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
             backends: wgpu::Backends::all(),
@@ -65,17 +250,12 @@ impl RenderContext {
             .expect("could not create device");
 
         // Configure surface
-        let surface_config =
-            create_surface_config(&window, &surface, &adapter, PresentMode::AutoVsync);
+        let surface_config = create_surface_config(&window, &surface, &adapter, present_mode);
         surface.configure(&device, &surface_config);
 
         // Create pipeline
-        let (render_pipeline, texture, diffuse_bind_group) = create_pipeline(
-            &device,
-            &surface_config,
-            DEFAULT_CANVAS_WIDTH,
-            DEFAULT_CANVAS_HEIGHT,
-        );
+        let (render_pipeline, texture, diffuse_bind_group) =
+            create_pipeline(&device, &surface_config, width, height);
 
         let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Vertex Buffer"),
@@ -89,11 +269,6 @@ impl RenderContext {
             usage: wgpu::BufferUsages::INDEX,
         });
 
-        // Media
-        let canvas = Canvas::new(DEFAULT_CANVAS_WIDTH, DEFAULT_CANVAS_HEIGHT);
-        let screenshot_uploader =
-            ScreenshotUploader::new(DEFAULT_CANVAS_WIDTH, DEFAULT_CANVAS_HEIGHT);
-
         Self {
             window,
             surface,
@@ -106,12 +281,10 @@ impl RenderContext {
             index_buffer,
             texture_bind_group: diffuse_bind_group,
             texture,
-            canvas,
-            screenshot_uploader,
         }
     }
 
-    pub(crate) fn resize_canvas_texture(&mut self, width: u32, height: u32) {
+    fn resize_canvas_texture(&mut self, width: u32, height: u32) {
         let (pipeline, texture, bind_group) =
             create_pipeline(&self.device, &self.surface_config, width, height);
         self.render_pipeline = pipeline;
@@ -119,12 +292,12 @@ impl RenderContext {
         self.texture_bind_group = bind_group;
     }
 
-    pub(crate) fn reconfigure_present_mode(&mut self, present_mode: wgpu::PresentMode) {
+    fn reconfigure_present_mode(&mut self, present_mode: wgpu::PresentMode) {
         self.surface_config.present_mode = present_mode;
         self.surface.configure(&self.device, &self.surface_config);
     }
 
-    pub(crate) fn resize_window(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
+    fn resize_window(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
         if new_size.width > 0 && new_size.height > 0 {
             self.surface_config.width = new_size.width;
             self.surface_config.height = new_size.height;
@@ -132,8 +305,13 @@ impl RenderContext {
         }
     }
 
-    pub(crate) fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
+    fn render(
+        &mut self,
+        canvas: &Canvas,
+        profile: &mut ProfileContext,
+    ) -> Result<(), wgpu::SurfaceError> {
         // Update texture
+        let start = Instant::now();
         self.queue.write_texture(
             wgpu::ImageCopyTexture {
                 texture: &self.texture,
@@ -141,16 +319,18 @@ impl RenderContext {
                 origin: wgpu::Origin3d::ZERO,
                 aspect: wgpu::TextureAspect::All,
             },
-            self.canvas.pixels.as_slice(),
+            canvas.pixels.as_slice(),
             wgpu::ImageDataLayout {
                 offset: 0,
-                bytes_per_row: Some(4 * self.canvas.width),
-                rows_per_image: Some(self.canvas.height),
+                bytes_per_row: Some(4 * canvas.width),
+                rows_per_image: Some(canvas.height),
             },
             self.texture.size(),
         );
+        profile.record("upload", start, start.elapsed());
 
         // Render texture
+        let start = Instant::now();
         let output = self.surface.get_current_texture()?;
         let view = output
             .texture
@@ -184,6 +364,7 @@ impl RenderContext {
 
         self.queue.submit(std::iter::once(encoder.finish()));
         output.present();
+        profile.record("present", start, start.elapsed());
 
         Ok(())
     }
@@ -0,0 +1,735 @@
+// Present the canvas in a terminal
+
+use crate::{
+    app::{Callbacks, Runner},
+    canvas::Canvas,
+    combo::ComboContext,
+    config::AppConfig,
+    context::Context,
+    debug::DebugContext,
+    graphics::{encode_kitty, encode_kitty_delete, encode_sixel},
+    input::{self, InputContext, Key, KeyCode, NamedKey, TextEdit},
+    profile::ProfileContext,
+    render::{RenderBackend, RenderContext},
+    time::{self, TimeContext},
+};
+use crossterm::{
+    cursor,
+    event::{
+        self, DisableBracketedPaste, DisableFocusChange, EnableBracketedPaste, EnableFocusChange,
+        Event, KeyEvent, KeyEventKind, KeyModifiers, KeyboardEnhancementFlags, ModifierKeyCode,
+        PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+    },
+    execute, terminal,
+};
+use std::{
+    io::{self, Stdout, Write},
+    time::{Duration, Instant},
+};
+use winit::{
+    event::Modifiers,
+    keyboard::{ModifiersState, NativeKeyCode, PhysicalKey},
+};
+
+/// Time between frames if no target fps is set, terminals have no vsync
+const FRAME_TIME: Duration = Duration::from_nanos(16_666_667);
+
+/// Upper half block, foreground is the top pixel and background the bottom pixel
+const HALF_BLOCK: &str = "\u{2580}";
+
+/// Image id used by the kitty graphics backend
+const KITTY_IMAGE_ID: u32 = 1;
+
+/// How the canvas is drawn in the terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TerminalMode {
+    /// ANSI half-block characters, works in any terminal with 24-bit color
+    HalfBlock,
+    /// Sixel graphics, each pixel drawn as a scale x scale square
+    Sixel { scale: u32 },
+    /// Kitty graphics protocol, each pixel drawn as a scale x scale square
+    Kitty { scale: u32 },
+}
+
+/// Two vertically stacked pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Cell {
+    top: [u8; 3],
+    bottom: [u8; 3],
+}
+
+/// Draws the canvas in the terminal
+///
+/// Half-blocks only emit cells which changed since the previous frame,
+/// graphics protocols only emit frames which changed
+pub(crate) struct TerminalRenderer {
+    out: Stdout,
+    buffer: Vec<u8>,
+    mode: TerminalMode,
+
+    // terminal size in cells
+    columns: u16,
+    rows: u16,
+
+    // cells presented last frame, None if unknown
+    cells: Vec<Option<Cell>>,
+    cells_columns: u16,
+    cells_rows: u16,
+    clear: bool,
+
+    // pixels presented last frame by graphics protocols
+    pixels: Vec<u8>,
+}
+
+impl TerminalRenderer {
+    pub(crate) fn new(columns: u16, rows: u16) -> Self {
+        Self {
+            out: io::stdout(),
+            buffer: Vec::new(),
+            mode: TerminalMode::HalfBlock,
+            columns,
+            rows,
+            cells: Vec::new(),
+            cells_columns: 0,
+            cells_rows: 0,
+            clear: true,
+            pixels: Vec::new(),
+        }
+    }
+
+    /// Sets how the canvas is drawn
+    pub(crate) fn set_mode(&mut self, mode: TerminalMode) {
+        self.mode = mode;
+        self.invalidate();
+    }
+
+    /// Sets the terminal size in cells
+    pub(crate) fn resize(&mut self, columns: u16, rows: u16) {
+        self.columns = columns;
+        self.rows = rows;
+        self.invalidate();
+    }
+
+    /// Forces a full redraw next frame
+    pub(crate) fn invalidate(&mut self) {
+        self.cells.clear();
+        self.pixels.clear();
+        self.clear = true;
+    }
+
+    /// Write the changed cells to stdout
+    pub(crate) fn present(&mut self, canvas: &Canvas) -> io::Result<()> {
+        self.encode(canvas);
+        if !self.buffer.is_empty() {
+            self.out.write_all(&self.buffer)?;
+            self.out.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Encode the changes since last frame into the internal buffer
+    fn encode(&mut self, canvas: &Canvas) -> &[u8] {
+        self.buffer.clear();
+
+        match self.mode {
+            TerminalMode::HalfBlock => self.encode_half_blocks(canvas),
+            TerminalMode::Sixel { scale } | TerminalMode::Kitty { scale } => {
+                if self.pixels == canvas.pixels {
+                    return &self.buffer;
+                }
+                self.pixels.clone_from(&canvas.pixels);
+
+                if self.clear {
+                    self.buffer.extend_from_slice(b"\x1b[0m\x1b[2J");
+                    self.clear = false;
+                }
+                self.buffer.extend_from_slice(b"\x1b[H");
+
+                // writing to a Vec can not fail
+                let _ = match self.mode {
+                    TerminalMode::Sixel { .. } => encode_sixel(
+                        &mut self.buffer,
+                        &canvas.pixels,
+                        canvas.width,
+                        canvas.height,
+                        scale,
+                    ),
+                    _ => encode_kitty_delete(&mut self.buffer, KITTY_IMAGE_ID).and_then(|_| {
+                        encode_kitty(
+                            &mut self.buffer,
+                            &canvas.pixels,
+                            canvas.width,
+                            canvas.height,
+                            scale,
+                            Some(KITTY_IMAGE_ID),
+                        )
+                    }),
+                };
+            }
+        }
+
+        &self.buffer
+    }
+
+    /// Encode the changed cells as half-blocks
+    ///
+    /// The canvas is presented 1:1 and clipped to the terminal size
+    fn encode_half_blocks(&mut self, canvas: &Canvas) {
+        let columns = self.columns.min(canvas.width.min(u16::MAX as u32) as u16);
+        let rows = self
+            .rows
+            .min(canvas.height.div_ceil(2).min(u16::MAX as u32) as u16);
+        if columns != self.cells_columns || rows != self.cells_rows {
+            self.invalidate();
+        }
+        if self.cells.is_empty() {
+            self.cells = vec![None; columns as usize * rows as usize];
+            self.cells_columns = columns;
+            self.cells_rows = rows;
+        }
+        if self.clear {
+            self.buffer.extend_from_slice(b"\x1b[0m\x1b[2J");
+            self.clear = false;
+        }
+
+        let mut cursor = None;
+        let mut fg = None;
+        let mut bg = None;
+        for row in 0..rows {
+            for column in 0..columns {
+                let (x, y) = (column as u32, row as u32 * 2);
+                let top = canvas.pixel_rgb(x, y);
+                let bottom = if y + 1 < canvas.height {
+                    canvas.pixel_rgb(x, y + 1)
+                } else {
+                    [0, 0, 0]
+                };
+                let cell = Cell { top, bottom };
+
+                let index = row as usize * columns as usize + column as usize;
+                if self.cells[index] == Some(cell) {
+                    continue;
+                }
+                self.cells[index] = Some(cell);
+
+                // writing to a Vec can not fail
+                if cursor != Some((column, row)) {
+                    let _ = write!(self.buffer, "\x1b[{};{}H", row + 1, column + 1);
+                }
+                if fg != Some(top) {
+                    let _ = write!(self.buffer, "\x1b[38;2;{};{};{}m", top[0], top[1], top[2]);
+                    fg = Some(top);
+                }
+                if bg != Some(bottom) {
+                    let _ = write!(
+                        self.buffer,
+                        "\x1b[48;2;{};{};{}m",
+                        bottom[0], bottom[1], bottom[2]
+                    );
+                    bg = Some(bottom);
+                }
+                self.buffer.extend_from_slice(HALF_BLOCK.as_bytes());
+                cursor = Some((column + 1, row));
+            }
+        }
+
+        if fg.is_some() {
+            self.buffer.extend_from_slice(b"\x1b[0m");
+        }
+    }
+}
+
+/// Puts the terminal in raw mode on the alternate screen
+///
+/// Restores the terminal when dropped
+struct TerminalGuard {
+    enhanced_keyboard: bool,
+}
+
+impl TerminalGuard {
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        let mut out = io::stdout();
+        execute!(
+            out,
+            terminal::EnterAlternateScreen,
+            cursor::Hide,
+            EnableFocusChange,
+            EnableBracketedPaste
+        )?;
+
+        // Key release events are only reported by terminals supporting the kitty keyboard protocol
+        let enhanced_keyboard = terminal::supports_keyboard_enhancement().unwrap_or(false);
+        if enhanced_keyboard {
+            execute!(
+                out,
+                PushKeyboardEnhancementFlags(
+                    KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES
+                        | KeyboardEnhancementFlags::REPORT_EVENT_TYPES
+                        | KeyboardEnhancementFlags::REPORT_ALL_KEYS_AS_ESCAPE_CODES
+                )
+            )?;
+        }
+
+        Ok(Self { enhanced_keyboard })
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let mut out = io::stdout();
+        if self.enhanced_keyboard {
+            let _ = execute!(out, PopKeyboardEnhancementFlags);
+        }
+        let _ = execute!(
+            out,
+            DisableBracketedPaste,
+            DisableFocusChange,
+            cursor::Show,
+            terminal::LeaveAlternateScreen
+        );
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Feeds terminal key events into the keyboard context
+struct TerminalInput {
+    report_releases: bool,
+    auto_released: Vec<(PhysicalKey, Key)>,
+}
+
+impl TerminalInput {
+    /// Handle all pending terminal events
+    ///
+    /// Returns true if Ctrl+C was pressed
+    fn poll<C: Callbacks>(&mut self, app: &mut Runner<C>, ctx: &mut Context) -> io::Result<bool> {
+        // Without release events keys are only held for the frame they were pressed in
+        if !self.report_releases {
+            for (physical, logical) in self.auto_released.drain(..) {
+                ctx.input.release_key(physical, logical);
+            }
+            ctx.input
+                .keyboard
+                .modifiers_changed(&Modifiers::from(ModifiersState::empty()));
+        }
+
+        while event::poll(Duration::ZERO)? {
+            match event::read()? {
+                Event::Key(event) => {
+                    if event.code == event::KeyCode::Char('c')
+                        && event.modifiers.contains(KeyModifiers::CONTROL)
+                    {
+                        return Ok(true);
+                    }
+                    self.handle_key(ctx, event);
+                }
+                // Pasted text arrives at once instead of as key presses
+                Event::Paste(text) => ctx.input.type_text(&text),
+                Event::FocusGained => app.callbacks.on_focus_changed(ctx, true),
+                Event::FocusLost => app.callbacks.on_focus_changed(ctx, false),
+                // Size is in pixels, one cell being two pixels tall
+                Event::Resize(columns, rows) => {
+                    if let RenderBackend::Terminal(terminal) = &mut ctx.render.backend {
+                        terminal.resize(columns, rows);
+                    }
+                    // Not the window size, which config::save would store
+                    app.callbacks
+                        .on_resize(ctx, columns as u32, rows as u32 * 2);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(false)
+    }
+
+    fn handle_key(&mut self, ctx: &mut Context, event: KeyEvent) {
+        ctx.input
+            .keyboard
+            .modifiers_changed(&Modifiers::from(map_modifiers(event.modifiers)));
+
+        let physical = map_physical_key(event.code);
+        if let Some(logical) = map_logical_key(event.code) {
+            match event.kind {
+                KeyEventKind::Press | KeyEventKind::Repeat => {
+                    let repeat = event.kind == KeyEventKind::Repeat;
+                    ctx.input.press_key(physical, logical.clone(), repeat);
+                    if !self.report_releases {
+                        self.auto_released.push((physical, logical));
+                    }
+                }
+                KeyEventKind::Release => ctx.input.release_key(physical, logical),
+            }
+        }
+
+        if event.kind != KeyEventKind::Release {
+            match event.code {
+                event::KeyCode::Backspace => ctx.input.text.edit(TextEdit::Backspace),
+                event::KeyCode::Delete => ctx.input.text.edit(TextEdit::Delete),
+                event::KeyCode::Enter => ctx.input.text.edit(TextEdit::Submit),
+                event::KeyCode::Char(c)
+                    if !input::is_shortcut(
+                        event.modifiers.contains(KeyModifiers::CONTROL),
+                        event.modifiers.contains(KeyModifiers::ALT),
+                    ) =>
+                {
+                    ctx.input.type_text(c.encode_utf8(&mut [0; 4]))
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn map_modifiers(modifiers: KeyModifiers) -> ModifiersState {
+    let mut state = ModifiersState::empty();
+    if modifiers.contains(KeyModifiers::SHIFT) {
+        state |= ModifiersState::SHIFT;
+    }
+    if modifiers.contains(KeyModifiers::CONTROL) {
+        state |= ModifiersState::CONTROL;
+    }
+    if modifiers.contains(KeyModifiers::ALT) {
+        state |= ModifiersState::ALT;
+    }
+    if modifiers.contains(KeyModifiers::SUPER) {
+        state |= ModifiersState::SUPER;
+    }
+    state
+}
+
+/// Maps a terminal key to a physical key
+///
+/// Characters without a KeyCode get their lowercase code point as native code,
+/// so holding several of them is tracked per key
+fn map_physical_key(code: event::KeyCode) -> PhysicalKey {
+    match (map_key(code), code) {
+        (Some(key), _) => PhysicalKey::Code(key),
+        (None, event::KeyCode::Char(c)) => {
+            let c = c.to_lowercase().next().unwrap_or(c);
+            PhysicalKey::Unidentified(NativeKeyCode::Xkb(c as u32))
+        }
+        (None, _) => PhysicalKey::Unidentified(NativeKeyCode::Unidentified),
+    }
+}
+
+/// Maps a terminal key to the logical key it produces
+fn map_logical_key(code: event::KeyCode) -> Option<Key> {
+    let key = match code {
+        event::KeyCode::Char(c) => return Some(Key::Character(c.to_string().into())),
+        event::KeyCode::Enter => NamedKey::Enter,
+        event::KeyCode::Esc => NamedKey::Escape,
+        event::KeyCode::Backspace => NamedKey::Backspace,
+        event::KeyCode::Tab | event::KeyCode::BackTab => NamedKey::Tab,
+        event::KeyCode::Left => NamedKey::ArrowLeft,
+        event::KeyCode::Right => NamedKey::ArrowRight,
+        event::KeyCode::Up => NamedKey::ArrowUp,
+        event::KeyCode::Down => NamedKey::ArrowDown,
+        event::KeyCode::Home => NamedKey::Home,
+        event::KeyCode::End => NamedKey::End,
+        event::KeyCode::PageUp => NamedKey::PageUp,
+        event::KeyCode::PageDown => NamedKey::PageDown,
+        event::KeyCode::Insert => NamedKey::Insert,
+        event::KeyCode::Delete => NamedKey::Delete,
+        event::KeyCode::F(n) => match n {
+            1 => NamedKey::F1,
+            2 => NamedKey::F2,
+            3 => NamedKey::F3,
+            4 => NamedKey::F4,
+            5 => NamedKey::F5,
+            6 => NamedKey::F6,
+            7 => NamedKey::F7,
+            8 => NamedKey::F8,
+            9 => NamedKey::F9,
+            10 => NamedKey::F10,
+            11 => NamedKey::F11,
+            12 => NamedKey::F12,
+            _ => return None,
+        },
+        event::KeyCode::Modifier(modifier) => match modifier {
+            ModifierKeyCode::LeftShift | ModifierKeyCode::RightShift => NamedKey::Shift,
+            ModifierKeyCode::LeftControl | ModifierKeyCode::RightControl => NamedKey::Control,
+            ModifierKeyCode::LeftAlt | ModifierKeyCode::RightAlt => NamedKey::Alt,
+            ModifierKeyCode::LeftSuper | ModifierKeyCode::RightSuper => NamedKey::Super,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(Key::Named(key))
+}
+
+/// Maps a terminal key to the physical key it is most likely produced by
+fn map_key(code: event::KeyCode) -> Option<KeyCode> {
+    let key = match code {
+        event::KeyCode::Char(c) => match c.to_ascii_lowercase() {
+            'a' => KeyCode::KeyA,
+            'b' => KeyCode::KeyB,
+            'c' => KeyCode::KeyC,
+            'd' => KeyCode::KeyD,
+            'e' => KeyCode::KeyE,
+            'f' => KeyCode::KeyF,
+            'g' => KeyCode::KeyG,
+            'h' => KeyCode::KeyH,
+            'i' => KeyCode::KeyI,
+            'j' => KeyCode::KeyJ,
+            'k' => KeyCode::KeyK,
+            'l' => KeyCode::KeyL,
+            'm' => KeyCode::KeyM,
+            'n' => KeyCode::KeyN,
+            'o' => KeyCode::KeyO,
+            'p' => KeyCode::KeyP,
+            'q' => KeyCode::KeyQ,
+            'r' => KeyCode::KeyR,
+            's' => KeyCode::KeyS,
+            't' => KeyCode::KeyT,
+            'u' => KeyCode::KeyU,
+            'v' => KeyCode::KeyV,
+            'w' => KeyCode::KeyW,
+            'x' => KeyCode::KeyX,
+            'y' => KeyCode::KeyY,
+            'z' => KeyCode::KeyZ,
+            '0' | ')' => KeyCode::Digit0,
+            '1' | '!' => KeyCode::Digit1,
+            '2' | '@' => KeyCode::Digit2,
+            '3' | '#' => KeyCode::Digit3,
+            '4' | '$' => KeyCode::Digit4,
+            '5' | '%' => KeyCode::Digit5,
+            '6' | '^' => KeyCode::Digit6,
+            '7' | '&' => KeyCode::Digit7,
+            '8' | '*' => KeyCode::Digit8,
+            '9' | '(' => KeyCode::Digit9,
+            ' ' => KeyCode::Space,
+            '-' | '_' => KeyCode::Minus,
+            '=' | '+' => KeyCode::Equal,
+            '[' | '{' => KeyCode::BracketLeft,
+            ']' | '}' => KeyCode::BracketRight,
+            '\\' | '|' => KeyCode::Backslash,
+            ';' | ':' => KeyCode::Semicolon,
+            '\'' | '"' => KeyCode::Quote,
+            ',' | '<' => KeyCode::Comma,
+            '.' | '>' => KeyCode::Period,
+            '/' | '?' => KeyCode::Slash,
+            '`' | '~' => KeyCode::Backquote,
+            _ => return None,
+        },
+        event::KeyCode::Enter => KeyCode::Enter,
+        event::KeyCode::Esc => KeyCode::Escape,
+        event::KeyCode::Backspace => KeyCode::Backspace,
+        event::KeyCode::Tab | event::KeyCode::BackTab => KeyCode::Tab,
+        event::KeyCode::Left => KeyCode::ArrowLeft,
+        event::KeyCode::Right => KeyCode::ArrowRight,
+        event::KeyCode::Up => KeyCode::ArrowUp,
+        event::KeyCode::Down => KeyCode::ArrowDown,
+        event::KeyCode::Home => KeyCode::Home,
+        event::KeyCode::End => KeyCode::End,
+        event::KeyCode::PageUp => KeyCode::PageUp,
+        event::KeyCode::PageDown => KeyCode::PageDown,
+        event::KeyCode::Insert => KeyCode::Insert,
+        event::KeyCode::Delete => KeyCode::Delete,
+        event::KeyCode::F(n) => match n {
+            1 => KeyCode::F1,
+            2 => KeyCode::F2,
+            3 => KeyCode::F3,
+            4 => KeyCode::F4,
+            5 => KeyCode::F5,
+            6 => KeyCode::F6,
+            7 => KeyCode::F7,
+            8 => KeyCode::F8,
+            9 => KeyCode::F9,
+            10 => KeyCode::F10,
+            11 => KeyCode::F11,
+            12 => KeyCode::F12,
+            _ => return None,
+        },
+        event::KeyCode::Modifier(modifier) => match modifier {
+            ModifierKeyCode::LeftShift => KeyCode::ShiftLeft,
+            ModifierKeyCode::RightShift => KeyCode::ShiftRight,
+            ModifierKeyCode::LeftControl => KeyCode::ControlLeft,
+            ModifierKeyCode::RightControl => KeyCode::ControlRight,
+            ModifierKeyCode::LeftAlt => KeyCode::AltLeft,
+            ModifierKeyCode::RightAlt => KeyCode::AltRight,
+            ModifierKeyCode::LeftSuper => KeyCode::SuperLeft,
+            ModifierKeyCode::RightSuper => KeyCode::SuperRight,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(key)
+}
+
+/// Runs the application in the terminal instead of a window
+///
+/// Calls user defined functions through the same Callbacks trait as [`crate::run`].
+/// The canvas defaults to the terminal size, one cell being two pixels tall.
+/// Use [`crate::App::run_terminal`] to run with a configuration instead.
+///
+/// Keys are held until released on terminals reporting key releases,
+/// otherwise only for the frame they were pressed (or repeated) in.
+///
+/// Ctrl+C exits the application.
+/// Window specific callbacks such as on_file_dropped are not called,
+/// on_resize is called with the terminal size in pixels
+pub fn run<C: Callbacks + 'static>(callbacks: C) {
+    let (columns, rows) = terminal::size().expect("could not get terminal size");
+    run_with_config(
+        callbacks,
+        AppConfig {
+            canvas_size: (columns as u32, rows as u32 * 2),
+            ..Default::default()
+        },
+    );
+}
+
+/// Runs the application in the terminal with the canvas size, bindings
+/// and debug overlay key of the configuration
+///
+/// Window settings such as the title and vsync are ignored
+pub(crate) fn run_with_config<C: Callbacks + 'static>(callbacks: C, config: AppConfig) {
+    env_logger::init();
+    let mut app = Runner { callbacks };
+
+    let guard = TerminalGuard::enter().expect("could not set up terminal");
+    let (columns, rows) = terminal::size().expect("could not get terminal size");
+
+    let (width, height) = config.canvas_size;
+    let mut ctx = Context {
+        render: RenderContext::new_terminal(TerminalRenderer::new(columns, rows), width, height),
+        time: TimeContext::default(),
+        input: InputContext::default(),
+        profile: ProfileContext::default(),
+        debug: DebugContext::default(),
+        config,
+        combo: ComboContext::default(),
+    };
+    ctx.input.text.clipboard.connect();
+    let mut input = TerminalInput {
+        report_releases: guard.enhanced_keyboard,
+        auto_released: Vec::new(),
+    };
+
+    app.callbacks.init(&mut ctx);
+
+    loop {
+        let frame_start = Instant::now();
+
+        match input.poll(&mut app, &mut ctx) {
+            Ok(false) => {}
+            Ok(true) => break,
+            Err(err) => {
+                log::error!("could not read terminal events: {err}");
+                break;
+            }
+        }
+
+        if app.update(&mut ctx) {
+            break;
+        }
+        let _ = ctx.render.render(&mut ctx.profile);
+
+        let deadline = ctx
+            .time
+            .next_frame_deadline()
+            .unwrap_or(frame_start + FRAME_TIME);
+        time::sleep_until(deadline);
+    }
+
+    app.callbacks.on_exit(&mut ctx);
+    drop(guard);
+}
+
+//
+// Commands
+//
+
+/// Sets how the canvas is drawn when running in the terminal
+///
+/// Does nothing when presenting to a window
+pub fn set_mode(ctx: &mut Context, mode: TerminalMode) {
+    if let RenderBackend::Terminal(terminal) = &mut ctx.render.backend {
+        terminal.set_mode(mode);
+    }
+}
+
+//
+// Tests
+//
+
+#[cfg(test)]
+mod tests {
+    use super::{map_key, TerminalInput, TerminalMode, TerminalRenderer};
+    use crate::{
+        canvas::Canvas,
+        input::{logical_key_pressed, KeyCode},
+        Context,
+    };
+    use crossterm::event::{self, KeyEvent, KeyEventKind, KeyModifiers};
+    use winit::keyboard::Key;
+
+    #[test]
+    fn test_encode_only_changed_cells() {
+        let mut terminal = TerminalRenderer::new(80, 24);
+        let mut canvas = Canvas::new(4, 4);
+        canvas.clear_screen(&[0, 0, 0]);
+
+        let full = String::from_utf8(terminal.encode(&canvas).to_vec()).unwrap();
+        assert_eq!(full.matches('\u{2580}').count(), 8);
+
+        assert!(terminal.encode(&canvas).is_empty());
+
+        canvas.write_pixel(3, 3, &[255, 0, 0]);
+        let diff = String::from_utf8(terminal.encode(&canvas).to_vec()).unwrap();
+        assert_eq!(diff.matches('\u{2580}').count(), 1);
+        assert!(diff.contains("\x1b[2;4H"));
+        assert!(diff.contains("\x1b[48;2;255;0;0m"));
+    }
+
+    #[test]
+    fn test_encode_clipped_to_terminal() {
+        let mut terminal = TerminalRenderer::new(2, 1);
+        let canvas = Canvas::new(8, 8);
+
+        let output = String::from_utf8(terminal.encode(&canvas).to_vec()).unwrap();
+        assert_eq!(output.matches('\u{2580}').count(), 2);
+    }
+
+    #[test]
+    fn test_encode_graphics_only_changed_frames() {
+        let mut terminal = TerminalRenderer::new(80, 24);
+        terminal.set_mode(TerminalMode::Sixel { scale: 2 });
+        let mut canvas = Canvas::new(4, 4);
+
+        assert!(terminal
+            .encode(&canvas)
+            .starts_with(b"\x1b[0m\x1b[2J\x1b[H\x1bPq"));
+        assert!(terminal.encode(&canvas).is_empty());
+
+        canvas.write_pixel(0, 0, &[255, 0, 0]);
+        assert!(terminal.encode(&canvas).starts_with(b"\x1b[H\x1bPq"));
+    }
+
+    #[test]
+    fn test_map_key() {
+        assert_eq!(map_key(event::KeyCode::Char('A')), Some(KeyCode::KeyA));
+        assert_eq!(map_key(event::KeyCode::Char(' ')), Some(KeyCode::Space));
+        assert_eq!(map_key(event::KeyCode::Up), Some(KeyCode::ArrowUp));
+        assert_eq!(map_key(event::KeyCode::Char('\u{e9}')), None);
+    }
+
+    #[test]
+    fn test_keys_without_keycode_are_tracked_separately() {
+        let mut ctx = Context::headless(8, 8);
+        let mut input = TerminalInput {
+            report_releases: true,
+            auto_released: Vec::new(),
+        };
+        let key =
+            |c, kind| KeyEvent::new_with_kind(event::KeyCode::Char(c), KeyModifiers::NONE, kind);
+
+        input.handle_key(&mut ctx, key('\u{e9}', KeyEventKind::Press));
+        input.handle_key(&mut ctx, key('\u{f6}', KeyEventKind::Press));
+        input.handle_key(&mut ctx, key('\u{e9}', KeyEventKind::Release));
+
+        assert!(!logical_key_pressed(&ctx, &Key::Character("\u{e9}".into())));
+        assert!(logical_key_pressed(&ctx, &Key::Character("\u{f6}".into())));
+    }
+}
@@ -1,59 +1,344 @@
 use crate::Context;
-use std::time::Instant;
+use std::{
+    collections::BTreeMap,
+    time::{Duration, Instant},
+};
 
 const MS_AVERAGE_SAMPLED_TICKS: usize = 100;
+const DEFAULT_MAX_FIXED_STEPS: u32 = 8;
+
+/// Remaining time before a frame deadline which is spun instead of slept
+/// since sleeping is not precise enough
+pub(crate) const FRAME_SPIN_THRESHOLD: Duration = Duration::from_millis(2);
+
+/// Summary of recently sampled durations in seconds
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct TimingStats {
+    pub last: f32,
+    pub average: f32,
+    pub min: f32,
+    pub max: f32,
+    pub p50: f32,
+    pub p95: f32,
+    pub p99: f32,
+    /// Amount of samples the stats are computed from
+    pub samples: usize,
+}
+
+impl TimingStats {
+    /// Stats over samples, where last is the most recent sample
+    pub(crate) fn from_samples(samples: &[f32], last: f32) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(f32::total_cmp);
+        let percentile = |p: f32| {
+            let rank = (p * (sorted.len() - 1) as f32).round() as usize;
+            sorted[rank]
+        };
+
+        Self {
+            last,
+            average: sorted.iter().sum::<f32>() / sorted.len() as f32,
+            min: sorted[0],
+            max: sorted[sorted.len() - 1],
+            p50: percentile(0.50),
+            p95: percentile(0.95),
+            p99: percentile(0.99),
+            samples: sorted.len(),
+        }
+    }
+}
+
+/// Source of time advancing the time subsystem each frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Clock {
+    /// Wall clock time
+    #[default]
+    Real,
+    /// Every frame advances time by the same duration
+    FixedStep(Duration),
+    /// Time only advances through [`advance_clock`] and [`set_clock_time`]
+    Manual,
+}
+
+/// Callback run when a timer fires
+pub(crate) type TimerCallback = Box<dyn FnMut(&mut Context)>;
+
+/// Handle to a timer created with [`after`] or [`every`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Timer(u64);
+
+struct TimerState {
+    remaining: f32,
+    interval: Option<f32>,
+    // times fired this frame
+    fired: u32,
+    finished: bool,
+}
 
 pub(crate) struct TimeContext {
-    // dt
+    // clock
+    clock: Clock,
     start_time: Instant,
+    // clock time since start at the start of the frame
+    elapsed: Duration,
+    // clock time since start the manual clock advanced to
+    manual_elapsed: Duration,
+    // wall clock time at the start of the frame
+    frame_start: Instant,
+
+    // dt
     last_time: Instant,
     delta_time: f32,
+    real_delta_time: f32,
+    time_scale: f32,
 
     // frame time
     frame_times: [f32; MS_AVERAGE_SAMPLED_TICKS],
     frame_index: usize,
     frame_time_avg: f32,
     frame_time_sum: f32,
+    frame_count: usize,
 
     time_since_start: f32,
+    game_time: f64,
+
+    // fixed timestep
+    fixed_timestep: Option<f32>,
+    fixed_accumulator: f32,
+    max_fixed_steps: u32,
+    in_fixed_update: bool,
+
+    // frame limiter
+    target_frame_time: Option<Duration>,
+
+    // timers, ordered by id so they fire in creation order
+    timers: BTreeMap<u64, TimerState>,
+    timer_callbacks: BTreeMap<u64, TimerCallback>,
+    next_timer_id: u64,
 }
 
 impl Default for TimeContext {
     fn default() -> Self {
         let start_time = Instant::now();
         Self {
+            clock: Clock::Real,
             start_time,
+            elapsed: Duration::ZERO,
+            manual_elapsed: Duration::ZERO,
+            frame_start: start_time,
+
             last_time: start_time,
             delta_time: 0.0,
+            real_delta_time: 0.0,
+            time_scale: 1.0,
 
             frame_times: [0.0; MS_AVERAGE_SAMPLED_TICKS],
             frame_index: 0,
             frame_time_avg: 0.0,
             frame_time_sum: 0.0,
+            frame_count: 0,
 
             time_since_start: 0.0,
+            game_time: 0.0,
+
+            fixed_timestep: None,
+            fixed_accumulator: 0.0,
+            max_fixed_steps: DEFAULT_MAX_FIXED_STEPS,
+            in_fixed_update: false,
+
+            target_frame_time: None,
+
+            timers: BTreeMap::new(),
+            timer_callbacks: BTreeMap::new(),
+            next_timer_id: 0,
         }
     }
 }
 
 impl TimeContext {
     pub(crate) fn update_time(&mut self) {
-        let now = Instant::now();
+        self.frame_start = Instant::now();
+        let elapsed = match self.clock {
+            Clock::Real => self.frame_start.duration_since(self.start_time),
+            Clock::FixedStep(step) => self.elapsed + step,
+            Clock::Manual => self.manual_elapsed.max(self.elapsed),
+        };
 
         // update dt
-        self.delta_time = now.duration_since(self.last_time).as_secs_f32();
+        self.real_delta_time = (elapsed - self.elapsed).as_secs_f32();
+        self.delta_time = self.real_delta_time * self.time_scale;
 
         // frame time
-        let (new_ms, old_ms) = (self.delta_time, self.frame_times[self.frame_index]);
+        let (new_ms, old_ms) = (self.real_delta_time, self.frame_times[self.frame_index]);
         self.frame_times[self.frame_index] = new_ms;
         self.frame_index = (self.frame_index + 1) % MS_AVERAGE_SAMPLED_TICKS;
         self.frame_time_sum += new_ms - old_ms;
         self.frame_time_avg = self.frame_time_sum / MS_AVERAGE_SAMPLED_TICKS as f32;
+        self.frame_count += 1;
 
         // time since start
-        self.time_since_start = Instant::now().duration_since(self.start_time).as_secs_f32();
+        self.time_since_start = elapsed.as_secs_f32();
+        self.game_time += self.delta_time as f64;
+
+        self.elapsed = elapsed;
+        self.last_time = self.start_time + elapsed;
+
+        // timers
+        self.advance_timers(self.delta_time);
+    }
+
+    /// Switch clock, continuing from the current time
+    pub(crate) fn set_clock(&mut self, clock: Clock) {
+        if clock == Clock::Real {
+            self.start_time = Instant::now()
+                .checked_sub(self.elapsed)
+                .unwrap_or(self.start_time);
+        }
+        self.manual_elapsed = self.elapsed;
+        self.clock = clock;
+    }
+
+    /// Advance all timers, removing one-shot timers which finished last frame
+    fn advance_timers(&mut self, dt: f32) {
+        self.timers.retain(|_, timer| !timer.finished);
+        self.timer_callbacks
+            .retain(|id, _| self.timers.contains_key(id));
+
+        for timer in self.timers.values_mut() {
+            timer.fired = 0;
+            timer.remaining -= dt;
+            if timer.remaining > 0.0 {
+                continue;
+            }
+            match timer.interval {
+                Some(interval) => {
+                    let fired = (-timer.remaining / interval) as u32 + 1;
+                    timer.remaining += fired as f32 * interval;
+                    timer.fired = fired;
+                }
+                None => {
+                    timer.fired = 1;
+                    timer.finished = true;
+                }
+            }
+        }
+    }
+
+    fn add_timer(&mut self, seconds: f32, interval: Option<f32>) -> Timer {
+        let id = self.next_timer_id;
+        self.next_timer_id += 1;
+        self.timers.insert(
+            id,
+            TimerState {
+                remaining: seconds,
+                interval,
+                fired: 0,
+                finished: false,
+            },
+        );
+        Timer(id)
+    }
+
+    /// Removes callbacks of timers which fired this frame in creation order
+    /// Along with how many times each fired
+    pub(crate) fn take_fired_callbacks(&mut self) -> Vec<(Timer, u32, TimerCallback)> {
+        let fired = self
+            .timer_callbacks
+            .keys()
+            .filter_map(|id| match self.timers.get(id) {
+                Some(timer) if timer.fired > 0 => Some((*id, timer.fired)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        fired
+            .into_iter()
+            .filter_map(|(id, times)| {
+                let callback = self.timer_callbacks.remove(&id)?;
+                Some((Timer(id), times, callback))
+            })
+            .collect()
+    }
+
+    /// If the timer has not been cancelled
+    pub(crate) fn has_timer(&self, timer: Timer) -> bool {
+        self.timers.contains_key(&timer.0)
+    }
 
-        self.last_time = now;
+    /// Gives back a callback taken with take_fired_callbacks
+    /// Dropped if the timer was cancelled or replaced meanwhile
+    pub(crate) fn restore_callback(&mut self, timer: Timer, callback: TimerCallback) {
+        if self.timers.contains_key(&timer.0) {
+            self.timer_callbacks.entry(timer.0).or_insert(callback);
+        }
+    }
+
+    /// Adds the last frame's delta time to the accumulator
+    /// Returns how many fixed updates should run this frame
+    ///
+    /// Excess time is dropped when more than max_fixed_steps are needed
+    /// to avoid falling further behind each frame
+    pub(crate) fn fixed_steps(&mut self) -> u32 {
+        let Some(step) = self.fixed_timestep else {
+            return 0;
+        };
+
+        self.fixed_accumulator += self.delta_time;
+        let steps = (self.fixed_accumulator / step) as u32;
+        if steps > self.max_fixed_steps {
+            self.fixed_accumulator %= step;
+            return self.max_fixed_steps;
+        }
+        self.fixed_accumulator -= steps as f32 * step;
+
+        steps
+    }
+
+    /// Stats over the recently sampled frame times
+    pub(crate) fn frame_stats(&self) -> TimingStats {
+        let samples = self.frame_count.min(MS_AVERAGE_SAMPLED_TICKS);
+        TimingStats::from_samples(&self.frame_times[..samples], self.real_delta_time)
+    }
+
+    /// Sampled frame times from oldest to newest
+    pub(crate) fn recent_frame_times(&self) -> impl Iterator<Item = f32> + '_ {
+        let samples = self.frame_count.min(MS_AVERAGE_SAMPLED_TICKS);
+        (MS_AVERAGE_SAMPLED_TICKS - samples..MS_AVERAGE_SAMPLED_TICKS)
+            .map(move |i| self.frame_times[(self.frame_index + i) % MS_AVERAGE_SAMPLED_TICKS])
+    }
+
+    /// When the next frame should start if a target fps is set
+    pub(crate) fn next_frame_deadline(&self) -> Option<Instant> {
+        self.target_frame_time
+            .map(|frame_time| self.frame_start + frame_time)
+    }
+
+    /// Marks fixed updates running, during which delta time is the fixed timestep
+    pub(crate) fn set_in_fixed_update(&mut self, in_fixed_update: bool) {
+        self.in_fixed_update = in_fixed_update;
+    }
+
+    /// How far between the previous and next fixed update the current frame is [0,1]
+    pub(crate) fn fixed_alpha(&self) -> f32 {
+        match self.fixed_timestep {
+            Some(step) => (self.fixed_accumulator / step).clamp(0.0, 1.0),
+            None => 1.0,
+        }
+    }
+}
+
+/// Sleeps until close to the deadline, then spins for precision
+pub(crate) fn sleep_until(deadline: Instant) {
+    let now = Instant::now();
+    if deadline > now + FRAME_SPIN_THRESHOLD {
+        std::thread::sleep(deadline - now - FRAME_SPIN_THRESHOLD);
+    }
+    while Instant::now() < deadline {
+        std::hint::spin_loop();
     }
 }
 
@@ -66,14 +351,77 @@ pub fn time_since_start(ctx: &Context) -> f32 {
     ctx.time.time_since_start
 }
 
+/// Number of frames since the start of the application, counting the current one
+pub fn frame_count(ctx: &Context) -> usize {
+    ctx.time.frame_count
+}
+
+/// Time since the start of the application scaled by the time scale
+///
+/// Only advances between frames
+pub fn game_time(ctx: &Context) -> f32 {
+    ctx.time.game_time as f32
+}
+
 /// Time at start of frame
+///
+/// Follows the clock, so it only matches the wall clock with [`Clock::Real`]
 pub fn current_time(ctx: &Context) -> Instant {
     ctx.time.last_time
 }
 
+/// Sets the source of time, continuing from the current time
+///
+/// Non real clocks make delta time, timers and fps deterministic,
+/// e.g. for tests and replays
+pub fn set_clock(ctx: &mut Context, clock: Clock) {
+    ctx.time.set_clock(clock);
+}
+
+/// Current source of time
+pub fn clock(ctx: &Context) -> Clock {
+    ctx.time.clock
+}
+
+/// Advances a [`Clock::Manual`] by duration, applied at the start of the next frame
+pub fn advance_clock(ctx: &mut Context, duration: Duration) {
+    ctx.time.manual_elapsed += duration;
+}
+
+/// Sets the time since start of a [`Clock::Manual`], applied at the start of the next frame
+///
+/// Useful for replaying recorded timestamps. Time never goes backwards
+pub fn set_clock_time(ctx: &mut Context, time_since_start: Duration) {
+    ctx.time.manual_elapsed = time_since_start;
+}
+
 /// Last frame's delta time
+///
+/// Scaled by the time scale. The fixed timestep while in fixed_update
 pub fn delta_time(ctx: &Context) -> f32 {
-    ctx.time.delta_time
+    match ctx.time.fixed_timestep {
+        Some(step) if ctx.time.in_fixed_update => step,
+        _ => ctx.time.delta_time,
+    }
+}
+
+/// Last frame's delta time unaffected by the time scale
+pub fn real_delta_time(ctx: &Context) -> f32 {
+    ctx.time.real_delta_time
+}
+
+/// Scale applied to delta time, fixed updates and timers
+///
+/// 1.0 is normal speed, 0.5 slow motion and 0.0 paused.
+/// Real time such as time_since_start and fps is unaffected
+pub fn set_time_scale(ctx: &mut Context, scale: f32) {
+    debug_assert!(scale >= 0.0, "time scale ({}) must not be negative", scale);
+    ctx.time.time_scale = scale.max(0.0);
+}
+
+/// Scale applied to delta time, fixed updates and timers
+pub fn time_scale(ctx: &Context) -> f32 {
+    ctx.time.time_scale
 }
 
 /// Frame time (seconds) averaged over recent frames
@@ -85,3 +433,342 @@ pub fn frame_time(ctx: &Context) -> f32 {
 pub fn fps(ctx: &Context) -> f32 {
     1.0 / ctx.time.frame_time_avg
 }
+
+/// Min, max, average and percentile frame times (seconds) over recent frames
+pub fn frame_stats(ctx: &Context) -> TimingStats {
+    ctx.time.frame_stats()
+}
+
+/// Timer which fires once after the specified (scaled) seconds
+pub fn after(ctx: &mut Context, seconds: f32) -> Timer {
+    ctx.time.add_timer(seconds, None)
+}
+
+/// Timer which fires repeatedly every specified (scaled) seconds
+///
+/// Panics if seconds is not positive
+pub fn every(ctx: &mut Context, seconds: f32) -> Timer {
+    assert!(
+        seconds > 0.0,
+        "timer interval ({}) must be positive",
+        seconds
+    );
+    ctx.time.add_timer(seconds, Some(seconds))
+}
+
+/// If the timer fired this frame
+pub fn timer_fired(ctx: &Context, timer: Timer) -> bool {
+    ctx.time
+        .timers
+        .get(&timer.0)
+        .is_some_and(|state| state.fired > 0)
+}
+
+/// How many times the timer fired this frame
+///
+/// Repeating timers can fire several times during long frames
+pub fn timer_fired_count(ctx: &Context, timer: Timer) -> u32 {
+    ctx.time.timers.get(&timer.0).map_or(0, |state| state.fired)
+}
+
+/// If a one-shot timer has fired or the timer was cancelled
+pub fn timer_finished(ctx: &Context, timer: Timer) -> bool {
+    ctx.time
+        .timers
+        .get(&timer.0)
+        .map_or(true, |state| state.finished)
+}
+
+/// Seconds until the timer fires next, 0.0 if finished
+pub fn timer_remaining(ctx: &Context, timer: Timer) -> f32 {
+    match ctx.time.timers.get(&timer.0) {
+        Some(state) if !state.finished => state.remaining.max(0.0),
+        _ => 0.0,
+    }
+}
+
+/// Stop the timer, it will not fire again
+pub fn cancel_timer(ctx: &mut Context, timer: Timer) {
+    ctx.time.timers.remove(&timer.0);
+    ctx.time.timer_callbacks.remove(&timer.0);
+}
+
+/// Run a callback each time the timer fires
+///
+/// Called at the start of the frame before fixed_update and update.
+/// Replaces any previous callback of the timer
+pub fn on_timer<F>(ctx: &mut Context, timer: Timer, callback: F)
+where
+    F: FnMut(&mut Context) + 'static,
+{
+    if ctx.time.timers.contains_key(&timer.0) {
+        ctx.time.timer_callbacks.insert(timer.0, Box::new(callback));
+    }
+}
+
+/// Limit the frame rate independent of vsync
+///
+/// None renders as fast as possible (or at the vsync rate)
+pub fn set_target_fps(ctx: &mut Context, fps: Option<u32>) {
+    ctx.time.target_frame_time = fps
+        .filter(|&fps| fps > 0)
+        .map(|fps| Duration::from_secs_f64(1.0 / fps as f64));
+}
+
+/// Frame rate limit if set
+pub fn target_fps(ctx: &Context) -> Option<u32> {
+    ctx.time
+        .target_frame_time
+        .map(|frame_time| (1.0 / frame_time.as_secs_f64()).round() as u32)
+}
+
+/// Enable/Disable calling fixed_update at a fixed rate
+///
+/// Timestep in seconds, e.g. Some(1.0 / 60.0) for 60 Hz
+///
+/// Panics if timestep is not positive
+pub fn set_fixed_timestep(ctx: &mut Context, timestep: Option<f32>) {
+    if let Some(step) = timestep {
+        assert!(step > 0.0, "fixed timestep ({}) must be positive", step);
+    }
+    ctx.time.fixed_timestep = timestep;
+    ctx.time.fixed_accumulator = 0.0;
+}
+
+/// Fixed timestep (seconds) if enabled
+pub fn fixed_timestep(ctx: &Context) -> Option<f32> {
+    ctx.time.fixed_timestep
+}
+
+/// Maximum amount of fixed updates per frame
+///
+/// Time exceeding this is dropped, slowing down the simulation instead of
+/// spending ever longer frames catching up
+pub fn set_max_fixed_steps(ctx: &mut Context, steps: u32) {
+    ctx.time.max_fixed_steps = steps;
+}
+
+/// Interpolation factor between the previous and current fixed update \[0,1\]
+///
+/// 1.0 if fixed timestep is disabled
+pub fn fixed_alpha(ctx: &Context) -> f32 {
+    ctx.time.fixed_alpha()
+}
+
+//
+// Tests
+//
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        delta_time, set_clock, set_fixed_timestep, Clock, TimeContext, Timer, TimingStats,
+    };
+    use crate::Context;
+    use std::time::Duration;
+
+    #[test]
+    fn test_fixed_steps_accumulate() {
+        let mut time = TimeContext {
+            fixed_timestep: Some(0.1),
+            ..Default::default()
+        };
+
+        time.delta_time = 0.05;
+        assert_eq!(time.fixed_steps(), 0);
+        assert!((time.fixed_alpha() - 0.5).abs() < 1e-4);
+
+        time.delta_time = 0.26;
+        assert_eq!(time.fixed_steps(), 3);
+        assert!((time.fixed_alpha() - 0.1).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_fixed_steps_clamped() {
+        let mut time = TimeContext {
+            fixed_timestep: Some(0.1),
+            max_fixed_steps: 4,
+            ..Default::default()
+        };
+
+        time.delta_time = 10.05;
+        assert_eq!(time.fixed_steps(), 4);
+        assert!(time.fixed_alpha() < 1.0);
+
+        time.delta_time = 0.0;
+        assert_eq!(time.fixed_steps(), 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_zero_fixed_timestep_panics() {
+        let mut ctx = Context::headless(8, 8);
+        set_fixed_timestep(&mut ctx, Some(0.0));
+    }
+
+    #[test]
+    fn test_timing_stats() {
+        let samples = (1..=100).map(|i| i as f32).collect::<Vec<_>>();
+        let stats = TimingStats::from_samples(&samples, 7.0);
+
+        assert_eq!(stats.last, 7.0);
+        assert_eq!(stats.min, 1.0);
+        assert_eq!(stats.max, 100.0);
+        assert_eq!(stats.average, 50.5);
+        assert_eq!(stats.p50, 51.0);
+        assert_eq!(stats.p95, 95.0);
+        assert_eq!(stats.p99, 99.0);
+        assert_eq!(stats.samples, 100);
+
+        assert_eq!(TimingStats::from_samples(&[], 0.0), TimingStats::default());
+    }
+
+    #[test]
+    fn test_frame_stats_ignore_unsampled_frames() {
+        let mut time = TimeContext::default();
+        time.frame_times[0] = 0.5;
+        time.frame_count = 1;
+
+        assert_eq!(time.frame_stats().min, 0.5);
+        assert_eq!(time.frame_stats().samples, 1);
+    }
+
+    #[test]
+    fn test_one_shot_timer() {
+        let mut time = TimeContext::default();
+        let Timer(id) = time.add_timer(1.0, None);
+
+        time.advance_timers(0.6);
+        assert_eq!(time.timers[&id].fired, 0);
+
+        time.advance_timers(0.6);
+        assert_eq!(time.timers[&id].fired, 1);
+        assert!(time.timers[&id].finished);
+
+        time.advance_timers(0.6);
+        assert!(!time.timers.contains_key(&id));
+    }
+
+    #[test]
+    fn test_repeating_timer() {
+        let mut time = TimeContext::default();
+        let Timer(id) = time.add_timer(0.5, Some(0.5));
+
+        time.advance_timers(0.6);
+        assert_eq!(time.timers[&id].fired, 1);
+
+        time.advance_timers(1.0);
+        assert_eq!(time.timers[&id].fired, 2);
+        assert!((time.timers[&id].remaining - 0.4).abs() < 1e-4);
+        assert!(!time.timers[&id].finished);
+    }
+
+    #[test]
+    fn test_fired_callbacks() {
+        let mut time = TimeContext::default();
+        let timer = time.add_timer(0.5, Some(0.5));
+        time.timer_callbacks.insert(timer.0, Box::new(|_| {}));
+
+        time.advance_timers(0.1);
+        assert!(time.take_fired_callbacks().is_empty());
+
+        time.advance_timers(1.0);
+        let fired = time.take_fired_callbacks();
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].1, 2);
+
+        for (timer, _, callback) in fired {
+            time.restore_callback(timer, callback);
+        }
+        assert!(time.timer_callbacks.contains_key(&timer.0));
+    }
+
+    #[test]
+    fn test_fired_callbacks_order() {
+        let mut time = TimeContext::default();
+        let timers = (0..16)
+            .map(|i| time.add_timer(0.1 + i as f32 * 0.01, None))
+            .collect::<Vec<_>>();
+        for timer in &timers {
+            time.timer_callbacks.insert(timer.0, Box::new(|_| {}));
+        }
+
+        time.advance_timers(1.0);
+        let fired = time
+            .take_fired_callbacks()
+            .into_iter()
+            .map(|(timer, _, _)| timer)
+            .collect::<Vec<_>>();
+        assert_eq!(fired, timers);
+    }
+
+    #[test]
+    fn test_fixed_step_clock() {
+        let mut time = TimeContext::default();
+        time.set_clock(Clock::FixedStep(Duration::from_millis(250)));
+        for _ in 0..4 {
+            time.update_time();
+        }
+
+        assert_eq!(time.real_delta_time, 0.25);
+        assert_eq!(time.time_since_start, 1.0);
+        assert_eq!(time.last_time, time.start_time + Duration::from_secs(1));
+        assert_eq!(time.frame_stats().average, 0.25);
+    }
+
+    #[test]
+    fn test_manual_clock() {
+        let mut time = TimeContext::default();
+        time.set_clock(Clock::Manual);
+        time.update_time();
+        assert_eq!(time.real_delta_time, 0.0);
+
+        time.manual_elapsed += Duration::from_millis(500);
+        time.update_time();
+        assert_eq!(time.real_delta_time, 0.5);
+        assert_eq!(time.time_since_start, 0.5);
+
+        // Never goes backwards
+        time.manual_elapsed = Duration::from_millis(100);
+        time.update_time();
+        assert_eq!(time.real_delta_time, 0.0);
+        assert_eq!(time.time_since_start, 0.5);
+    }
+
+    #[test]
+    fn test_next_frame_deadline() {
+        let mut time = TimeContext::default();
+        assert_eq!(time.next_frame_deadline(), None);
+
+        time.target_frame_time = Some(Duration::from_millis(20));
+        assert_eq!(
+            time.next_frame_deadline(),
+            Some(time.frame_start + Duration::from_millis(20))
+        );
+    }
+
+    #[test]
+    fn test_fixed_steps_disabled() {
+        let mut time = TimeContext {
+            delta_time: 1.0,
+            ..Default::default()
+        };
+
+        assert_eq!(time.fixed_steps(), 0);
+        assert_eq!(time.fixed_alpha(), 1.0);
+    }
+
+    #[test]
+    fn test_delta_time_in_fixed_update() {
+        let mut ctx = Context::headless(1, 1);
+        set_clock(&mut ctx, Clock::FixedStep(Duration::from_millis(250)));
+        set_fixed_timestep(&mut ctx, Some(0.125));
+        ctx.time.update_time();
+
+        assert_eq!(delta_time(&ctx), 0.25);
+        ctx.time.set_in_fixed_update(true);
+        assert_eq!(delta_time(&ctx), 0.125);
+        ctx.time.set_in_fixed_update(false);
+        assert_eq!(delta_time(&ctx), 0.25);
+    }
+}
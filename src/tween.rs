@@ -0,0 +1,515 @@
+// Tweening and easing
+
+use crate::{time, Context};
+use std::f32::consts::PI;
+
+/// Easing functions mapping linear progress \[0,1\] to eased progress
+///
+/// See <https://easings.net> for plots of each function
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Easing {
+    #[default]
+    Linear,
+    InQuad,
+    OutQuad,
+    InOutQuad,
+    InCubic,
+    OutCubic,
+    InOutCubic,
+    InQuart,
+    OutQuart,
+    InOutQuart,
+    InQuint,
+    OutQuint,
+    InOutQuint,
+    InSine,
+    OutSine,
+    InOutSine,
+    InExpo,
+    OutExpo,
+    InOutExpo,
+    InCirc,
+    OutCirc,
+    InOutCirc,
+    InBack,
+    OutBack,
+    InOutBack,
+    InElastic,
+    OutElastic,
+    InOutElastic,
+    InBounce,
+    OutBounce,
+    InOutBounce,
+}
+
+impl Easing {
+    /// Eased progress for linear progress t
+    ///
+    /// t is clamped to \[0,1\]. Back and elastic easings overshoot the range
+    pub fn apply(self, t: f32) -> f32 {
+        const C1: f32 = 1.70158;
+        const C2: f32 = C1 * 1.525;
+        const C3: f32 = C1 + 1.0;
+        const C4: f32 = 2.0 * PI / 3.0;
+        const C5: f32 = 2.0 * PI / 4.5;
+
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::InQuad => t * t,
+            Easing::OutQuad => 1.0 - (1.0 - t).powi(2),
+            Easing::InOutQuad => in_out(t, |t| t * t),
+            Easing::InCubic => t.powi(3),
+            Easing::OutCubic => 1.0 - (1.0 - t).powi(3),
+            Easing::InOutCubic => in_out(t, |t| t.powi(3)),
+            Easing::InQuart => t.powi(4),
+            Easing::OutQuart => 1.0 - (1.0 - t).powi(4),
+            Easing::InOutQuart => in_out(t, |t| t.powi(4)),
+            Easing::InQuint => t.powi(5),
+            Easing::OutQuint => 1.0 - (1.0 - t).powi(5),
+            Easing::InOutQuint => in_out(t, |t| t.powi(5)),
+            Easing::InSine => 1.0 - (t * PI / 2.0).cos(),
+            Easing::OutSine => (t * PI / 2.0).sin(),
+            Easing::InOutSine => -((PI * t).cos() - 1.0) / 2.0,
+            Easing::InExpo => expo(t),
+            Easing::OutExpo => 1.0 - expo(1.0 - t),
+            Easing::InOutExpo => in_out(t, expo),
+            Easing::InCirc => 1.0 - (1.0 - t * t).sqrt(),
+            Easing::OutCirc => (1.0 - (t - 1.0).powi(2)).sqrt(),
+            Easing::InOutCirc => in_out(t, |t| 1.0 - (1.0 - t * t).sqrt()),
+            Easing::InBack => C3 * t.powi(3) - C1 * t * t,
+            Easing::OutBack => 1.0 + C3 * (t - 1.0).powi(3) + C1 * (t - 1.0).powi(2),
+            Easing::InOutBack => in_out(t, |t| (C2 + 1.0) * t.powi(3) - C2 * t * t),
+            Easing::InElastic => elastic(t, C4),
+            Easing::OutElastic => 1.0 - elastic(1.0 - t, C4),
+            Easing::InOutElastic => in_out_elastic(t, C5),
+            Easing::InBounce => 1.0 - bounce(1.0 - t),
+            Easing::OutBounce => bounce(t),
+            Easing::InOutBounce => in_out(t, |t| 1.0 - bounce(1.0 - t)),
+        }
+    }
+}
+
+/// Mirror an ease in function into an ease in-out function
+fn in_out(t: f32, ease_in: impl Fn(f32) -> f32) -> f32 {
+    if t < 0.5 {
+        ease_in(t * 2.0) / 2.0
+    } else {
+        1.0 - ease_in((1.0 - t) * 2.0) / 2.0
+    }
+}
+
+fn expo(t: f32) -> f32 {
+    if t == 0.0 {
+        0.0
+    } else {
+        2f32.powf(10.0 * t - 10.0)
+    }
+}
+
+fn elastic(t: f32, period: f32) -> f32 {
+    if t == 0.0 || t == 1.0 {
+        t
+    } else {
+        -(2f32.powf(10.0 * t - 10.0)) * ((t * 10.0 - 10.75) * period).sin()
+    }
+}
+
+fn in_out_elastic(t: f32, period: f32) -> f32 {
+    if t == 0.0 || t == 1.0 {
+        t
+    } else if t < 0.5 {
+        -(2f32.powf(20.0 * t - 10.0) * ((20.0 * t - 11.125) * period).sin()) / 2.0
+    } else {
+        2f32.powf(-20.0 * t + 10.0) * ((20.0 * t - 11.125) * period).sin() / 2.0 + 1.0
+    }
+}
+
+fn bounce(t: f32) -> f32 {
+    const N1: f32 = 7.5625;
+    const D1: f32 = 2.75;
+
+    if t < 1.0 / D1 {
+        N1 * t * t
+    } else if t < 2.0 / D1 {
+        let t = t - 1.5 / D1;
+        N1 * t * t + 0.75
+    } else if t < 2.5 / D1 {
+        let t = t - 2.25 / D1;
+        N1 * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / D1;
+        N1 * t * t + 0.984375
+    }
+}
+
+/// Values which can be interpolated
+pub trait Lerp: Copy {
+    /// Value between self (t = 0) and other (t = 1)
+    fn lerp(self, other: Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Lerp for (f32, f32) {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        (self.0.lerp(other.0, t), self.1.lerp(other.1, t))
+    }
+}
+
+impl<const N: usize> Lerp for [f32; N] {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        std::array::from_fn(|i| self[i].lerp(other[i], t))
+    }
+}
+
+/// Colors \[0,255\], rounded and clamped
+impl<const N: usize> Lerp for [u8; N] {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        std::array::from_fn(|i| {
+            (self[i] as f32)
+                .lerp(other[i] as f32, t)
+                .round()
+                .clamp(0.0, 255.0) as u8
+        })
+    }
+}
+
+/// Something with a value over a fixed duration
+pub trait Animation {
+    type Value;
+
+    /// Length in seconds
+    fn duration(&self) -> f32;
+
+    /// Value at elapsed seconds since the start
+    ///
+    /// Elapsed is clamped to \[0,duration\]
+    fn sample(&self, elapsed: f32) -> Self::Value;
+}
+
+/// Interpolates between two values over a duration
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tween<T: Lerp> {
+    pub from: T,
+    pub to: T,
+    pub duration: f32,
+    pub easing: Easing,
+}
+
+impl<T: Lerp> Tween<T> {
+    /// Linear tween from one value to another over duration seconds
+    pub fn new(from: T, to: T, duration: f32) -> Self {
+        Self {
+            from,
+            to,
+            duration,
+            easing: Easing::Linear,
+        }
+    }
+
+    /// Holds a value for duration seconds, useful as a delay in sequences
+    pub fn hold(value: T, duration: f32) -> Self {
+        Self::new(value, value, duration)
+    }
+
+    /// Sets the easing function
+    pub fn easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+}
+
+impl<T: Lerp> Animation for Tween<T> {
+    type Value = T;
+
+    fn duration(&self) -> f32 {
+        self.duration
+    }
+
+    fn sample(&self, elapsed: f32) -> T {
+        let t = if self.duration > 0.0 {
+            elapsed / self.duration
+        } else {
+            1.0
+        };
+        self.from.lerp(self.to, self.easing.apply(t))
+    }
+}
+
+/// Animations played one after another
+///
+/// The value is the value of the currently playing animation
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sequence<A: Animation> {
+    animations: Vec<A>,
+}
+
+impl<A: Animation> Sequence<A> {
+    /// Panics if animations is empty
+    pub fn new(animations: Vec<A>) -> Self {
+        assert!(!animations.is_empty(), "sequence must not be empty");
+        Self { animations }
+    }
+
+    /// Appends an animation to the end
+    pub fn then(mut self, animation: A) -> Self {
+        self.animations.push(animation);
+        self
+    }
+}
+
+impl<A: Animation> Animation for Sequence<A> {
+    type Value = A::Value;
+
+    fn duration(&self) -> f32 {
+        self.animations.iter().map(A::duration).sum()
+    }
+
+    fn sample(&self, elapsed: f32) -> A::Value {
+        let mut start = 0.0;
+        for animation in &self.animations[..self.animations.len() - 1] {
+            let end = start + animation.duration();
+            if elapsed < end {
+                return animation.sample(elapsed - start);
+            }
+            start = end;
+        }
+        let last = &self.animations[self.animations.len() - 1];
+        last.sample(elapsed - start)
+    }
+}
+
+/// Animations played at the same time
+///
+/// The value contains the value of each animation.
+/// Tuples of animations are also played in parallel
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parallel<A: Animation> {
+    animations: Vec<A>,
+}
+
+impl<A: Animation> Parallel<A> {
+    pub fn new(animations: Vec<A>) -> Self {
+        Self { animations }
+    }
+
+    /// Adds an animation
+    pub fn with(mut self, animation: A) -> Self {
+        self.animations.push(animation);
+        self
+    }
+}
+
+impl<A: Animation> Animation for Parallel<A> {
+    type Value = Vec<A::Value>;
+
+    fn duration(&self) -> f32 {
+        self.animations.iter().map(A::duration).fold(0.0, f32::max)
+    }
+
+    fn sample(&self, elapsed: f32) -> Vec<A::Value> {
+        self.animations
+            .iter()
+            .map(|animation| animation.sample(elapsed))
+            .collect()
+    }
+}
+
+macro_rules! impl_parallel_tuple {
+    ($($name:ident $index:tt),+) => {
+        impl<$($name: Animation),+> Animation for ($($name,)+) {
+            type Value = ($($name::Value,)+);
+
+            fn duration(&self) -> f32 {
+                0.0f32 $(.max(self.$index.duration()))+
+            }
+
+            fn sample(&self, elapsed: f32) -> Self::Value {
+                ($(self.$index.sample(elapsed),)+)
+            }
+        }
+    };
+}
+
+impl_parallel_tuple!(A 0, B 1);
+impl_parallel_tuple!(A 0, B 1, C 2);
+impl_parallel_tuple!(A 0, B 1, C 2, D 3);
+
+/// Plays an animation, advancing automatically with the scaled game time
+///
+/// ```no_run
+/// # use pixelated::{tween::{self, Easing, Tween}, Context};
+/// # fn update(ctx: &mut Context) {
+/// let fade = tween::play(ctx, Tween::new([0u8, 0, 0], [255, 255, 255], 0.5).easing(Easing::OutQuad));
+/// // later frames
+/// let color = fade.value(ctx);
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Player<A: Animation> {
+    pub animation: A,
+    start: f32,
+    looping: bool,
+}
+
+impl<A: Animation> Player<A> {
+    /// Restart from the beginning on completion
+    pub fn looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    /// Seconds since the animation started, wrapped when looping
+    pub fn elapsed(&self, ctx: &Context) -> f32 {
+        let elapsed = (time::game_time(ctx) - self.start).max(0.0);
+        let duration = self.animation.duration();
+        if self.looping && duration > 0.0 {
+            elapsed % duration
+        } else {
+            elapsed.min(duration)
+        }
+    }
+
+    /// Current value
+    pub fn value(&self, ctx: &Context) -> A::Value {
+        self.animation.sample(self.elapsed(ctx))
+    }
+
+    /// Linear progress \[0,1\]
+    pub fn progress(&self, ctx: &Context) -> f32 {
+        let duration = self.animation.duration();
+        if duration > 0.0 {
+            self.elapsed(ctx) / duration
+        } else {
+            1.0
+        }
+    }
+
+    /// If the animation has played to the end, never true when looping
+    pub fn finished(&self, ctx: &Context) -> bool {
+        !self.looping && time::game_time(ctx) - self.start >= self.animation.duration()
+    }
+
+    /// Play again from the beginning
+    pub fn restart(&mut self, ctx: &Context) {
+        self.start = time::game_time(ctx);
+    }
+}
+
+//
+// Commands
+//
+
+/// Start playing an animation from the current game time
+pub fn play<A: Animation>(ctx: &Context, animation: A) -> Player<A> {
+    Player {
+        animation,
+        start: time::game_time(ctx),
+        looping: false,
+    }
+}
+
+//
+// Tests
+//
+
+#[cfg(test)]
+mod tests {
+    use super::{Animation, Easing, Lerp, Parallel, Sequence, Tween};
+
+    const ALL_EASINGS: [Easing; 31] = [
+        Easing::Linear,
+        Easing::InQuad,
+        Easing::OutQuad,
+        Easing::InOutQuad,
+        Easing::InCubic,
+        Easing::OutCubic,
+        Easing::InOutCubic,
+        Easing::InQuart,
+        Easing::OutQuart,
+        Easing::InOutQuart,
+        Easing::InQuint,
+        Easing::OutQuint,
+        Easing::InOutQuint,
+        Easing::InSine,
+        Easing::OutSine,
+        Easing::InOutSine,
+        Easing::InExpo,
+        Easing::OutExpo,
+        Easing::InOutExpo,
+        Easing::InCirc,
+        Easing::OutCirc,
+        Easing::InOutCirc,
+        Easing::InBack,
+        Easing::OutBack,
+        Easing::InOutBack,
+        Easing::InElastic,
+        Easing::OutElastic,
+        Easing::InOutElastic,
+        Easing::InBounce,
+        Easing::OutBounce,
+        Easing::InOutBounce,
+    ];
+
+    #[test]
+    fn test_easing_endpoints() {
+        for easing in ALL_EASINGS {
+            assert!(easing.apply(0.0).abs() < 1e-3, "{:?} at 0", easing);
+            assert!((easing.apply(1.0) - 1.0).abs() < 1e-3, "{:?} at 1", easing);
+        }
+    }
+
+    #[test]
+    fn test_in_out_symmetric() {
+        assert!((Easing::InOutCubic.apply(0.5) - 0.5).abs() < 1e-5);
+        assert!((Easing::InOutQuad.apply(0.25) - 0.125).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_lerp_color() {
+        assert_eq!([0u8, 100, 255].lerp([255, 200, 255], 0.5), [128, 150, 255]);
+        assert_eq!([0.0f32, 1.0].lerp([1.0, 0.0], 0.25), [0.25, 0.75]);
+    }
+
+    #[test]
+    fn test_tween_sample() {
+        let tween = Tween::new(10.0, 20.0, 2.0);
+
+        assert_eq!(tween.sample(-1.0), 10.0);
+        assert_eq!(tween.sample(1.0), 15.0);
+        assert_eq!(tween.sample(5.0), 20.0);
+    }
+
+    #[test]
+    fn test_sequence() {
+        let sequence = Sequence::new(vec![Tween::new(0.0, 1.0, 1.0)])
+            .then(Tween::hold(1.0, 1.0))
+            .then(Tween::new(1.0, 0.0, 2.0));
+
+        assert_eq!(sequence.duration(), 4.0);
+        assert_eq!(sequence.sample(0.5), 0.5);
+        assert_eq!(sequence.sample(1.5), 1.0);
+        assert_eq!(sequence.sample(3.0), 0.5);
+        assert_eq!(sequence.sample(10.0), 0.0);
+    }
+
+    #[test]
+    fn test_parallel() {
+        let parallel =
+            Parallel::new(vec![Tween::new(0.0, 1.0, 1.0)]).with(Tween::new(0.0, 4.0, 2.0));
+        assert_eq!(parallel.duration(), 2.0);
+        assert_eq!(parallel.sample(1.0), vec![1.0, 2.0]);
+
+        let tuple = (
+            Tween::new(0.0, 1.0, 1.0),
+            Tween::new([0u8; 3], [255; 3], 3.0),
+        );
+        assert_eq!(tuple.duration(), 3.0);
+        assert_eq!(tuple.sample(3.0), (1.0, [255; 3]));
+    }
+}
@@ -5,95 +5,243 @@ pub use winit;
 
 // Imports
 use crate::{
-    app::{App, Callbacks},
+    app::{Callbacks, Runner},
+    canvas::Canvas,
+    config::AppConfig,
     context::Context,
+    input::{self, InputEventKind, KeyModifier, TextEdit},
+    time::{self, FRAME_SPIN_THRESHOLD},
 };
+use image::ImageResult;
+use std::{path::Path, time::Instant};
 use winit::{
-    event::{DeviceEvent, ElementState, Event, WindowEvent},
-    event_loop::EventLoop,
-    keyboard::PhysicalKey,
-    window::WindowBuilder,
+    event::{DeviceEvent, ElementState, Event, Ime, WindowEvent},
+    event_loop::{ControlFlow, EventLoop},
+    keyboard::{Key, NamedKey},
+    window::{CursorGrabMode, Icon, WindowBuilder},
 };
 
-pub(crate) fn new_window() -> (winit::window::Window, winit::event_loop::EventLoop<()>) {
+pub(crate) fn new_window(
+    config: &AppConfig,
+) -> (winit::window::Window, winit::event_loop::EventLoop<()>) {
     let event_loop = EventLoop::new().expect("could not create event loop");
 
-    let window = WindowBuilder::new()
-        .build(&event_loop)
-        .expect("could not build window");
+    let mut builder = WindowBuilder::new()
+        .with_title(&config.title)
+        .with_resizable(config.resizable)
+        .with_window_icon(config.icon.as_deref().and_then(load_icon));
+
+    let (canvas_width, canvas_height) = config.canvas_size;
+    let window_size = match (config.window_size, config.window_scale) {
+        (Some(size), _) => Some(size),
+        (None, Some(scale)) => Some((canvas_width * scale, canvas_height * scale)),
+        (None, None) => None,
+    };
+    if let Some((width, height)) = window_size {
+        builder = builder.with_inner_size(winit::dpi::LogicalSize::new(width, height));
+    }
+
+    let window = builder.build(&event_loop).expect("could not build window");
 
     (window, event_loop)
 }
 
+/// Load a window icon from an image file
+fn load_icon(path: &str) -> Option<Icon> {
+    let image = match image::open(path) {
+        Ok(image) => image.into_rgba8(),
+        Err(err) => {
+            log::error!("could not load icon {path}: {err}");
+            return None;
+        }
+    };
+    let (width, height) = image.dimensions();
+    match Icon::from_rgba(image.into_raw(), width, height) {
+        Ok(icon) => Some(icon),
+        Err(err) => {
+            log::error!("invalid icon {path}: {err}");
+            None
+        }
+    }
+}
+
 pub(crate) async fn run_window<C: Callbacks + 'static>(
     event_loop: EventLoop<()>,
-    mut app: App<C>,
+    mut app: Runner<C>,
     mut ctx: Context,
 ) {
-    event_loop.set_control_flow(winit::event_loop::ControlFlow::Poll);
+    ctx.input.text.clipboard.connect();
+    event_loop.set_control_flow(ControlFlow::Poll);
     let res = event_loop.run(move |event, target| match event {
-        Event::WindowEvent { ref event, .. } => match event {
-            WindowEvent::RedrawRequested => {
-                if app.update(&mut ctx) {
+        Event::WindowEvent { ref event, .. } => {
+            app.callbacks.on_window_event(&mut ctx, event);
+
+            match event {
+                WindowEvent::RedrawRequested => {
+                    if app.update(&mut ctx) {
+                        target.exit();
+                    }
+                    match ctx.render.render(&mut ctx.profile) {
+                        Ok(_) => {}
+                        Err(wgpu::SurfaceError::Lost) => {
+                            let (width, height) = ctx.render.window_size();
+                            ctx.render
+                                .resize_window(winit::dpi::PhysicalSize::new(width, height))
+                        }
+                        Err(wgpu::SurfaceError::OutOfMemory) => {
+                            target.exit();
+                        }
+                        Err(e) => eprintln!("{:?}", e),
+                    }
+                }
+
+                WindowEvent::CloseRequested => {
                     target.exit();
                 }
-                match ctx.render.render() {
-                    Ok(_) => {}
-                    Err(wgpu::SurfaceError::Lost) => {
-                        ctx.render.resize_window(ctx.render.window.inner_size())
+                WindowEvent::Resized(new_size) => {
+                    ctx.render.resize_window(*new_size);
+                    if let Some(window) = ctx.render.window() {
+                        let size = new_size.to_logical::<u32>(window.scale_factor());
+                        ctx.config.window_size = Some((size.width, size.height));
                     }
-                    Err(wgpu::SurfaceError::OutOfMemory) => {
-                        target.exit();
+                    app.callbacks
+                        .on_resize(&mut ctx, new_size.width, new_size.height);
+                }
+                WindowEvent::Focused(focused) => {
+                    app.callbacks.on_focus_changed(&mut ctx, *focused);
+                }
+                WindowEvent::DroppedFile(path) => {
+                    app.callbacks.on_file_dropped(&mut ctx, path.clone());
+                }
+                // Mouse input
+                WindowEvent::CursorMoved { position, .. } => {
+                    ctx.input.mouse.set_pos(position.x, position.y);
+                    ctx.input.record(InputEventKind::MouseMoved {
+                        x: position.x,
+                        y: position.y,
+                    });
+                }
+                WindowEvent::CursorLeft { .. } => {
+                    ctx.input.mouse.set_on_screen(false);
+                    ctx.input.record(InputEventKind::MouseLeft);
+                }
+                WindowEvent::CursorEntered { .. } => {
+                    ctx.input.mouse.set_on_screen(true);
+                    ctx.input.record(InputEventKind::MouseEntered);
+                }
+                WindowEvent::MouseInput { state, button, .. } => match state {
+                    ElementState::Pressed => {
+                        ctx.input.mouse.press_button(*button);
+                        ctx.input
+                            .record(InputEventKind::MouseButtonPressed(*button));
                     }
-                    Err(e) => eprintln!("{:?}", e),
+                    ElementState::Released => {
+                        ctx.input.mouse.release_button(*button);
+                        ctx.input
+                            .record(InputEventKind::MouseButtonReleased(*button));
+                    }
+                },
+                WindowEvent::MouseWheel { delta, .. } => {
+                    let (x, y) = match delta {
+                        winit::event::MouseScrollDelta::LineDelta(x, y) => (*x as f64, *y as f64),
+                        winit::event::MouseScrollDelta::PixelDelta(pos) => (pos.x, pos.y),
+                    };
+                    ctx.input.mouse.set_scroll_delta((x, y));
+                    ctx.input
+                        .record(InputEventKind::MouseWheel { dx: x, dy: y });
                 }
-            }
+                // Touch input
+                WindowEvent::Touch(touch) => {
+                    ctx.input.touch(
+                        touch.id,
+                        touch.phase,
+                        (touch.location.x, touch.location.y),
+                        touch.force.map(|force| force.normalized() as f32),
+                    );
+                }
+                // Keyboard input
+                WindowEvent::ModifiersChanged(modifiers) => {
+                    ctx.input.keyboard.modifiers_changed(modifiers)
+                }
+                WindowEvent::KeyboardInput { event, .. } => {
+                    let logical = event.logical_key.clone();
+                    if event.state.is_pressed() {
+                        ctx.input
+                            .press_key(event.physical_key, logical, event.repeat);
+                    } else {
+                        ctx.input.release_key(event.physical_key, logical);
+                    }
 
-            WindowEvent::CloseRequested => {
-                target.exit();
-            }
-            WindowEvent::Resized(new_size) => {
-                ctx.render.resize_window(*new_size);
-            }
-            // Mouse input
-            WindowEvent::CursorMoved { position, .. } => {
-                ctx.input.mouse.set_pos(position.x, position.y);
-            }
-            WindowEvent::CursorLeft { .. } => ctx.input.mouse.set_on_screen(false),
-            WindowEvent::CursorEntered { .. } => ctx.input.mouse.set_on_screen(true),
-            WindowEvent::MouseInput { state, button, .. } => match state {
-                ElementState::Pressed => ctx.input.mouse.press_button(*button),
-                ElementState::Released => ctx.input.mouse.release_button(*button),
-            },
-            WindowEvent::MouseWheel { delta, .. } => {
-                let (x, y) = match delta {
-                    winit::event::MouseScrollDelta::LineDelta(x, y) => (*x as f64, *y as f64),
-                    winit::event::MouseScrollDelta::PixelDelta(pos) => (pos.x, pos.y),
-                };
-                ctx.input.mouse.set_scroll_delta((x, y));
-            }
-            // Keyboard input
-            WindowEvent::ModifiersChanged(modifiers) => {
-                ctx.input.keyboard.modifiers_changed(modifiers)
-            }
-            WindowEvent::KeyboardInput { event, .. } => {
-                let (key, pressed) = (event.physical_key, event.state.is_pressed());
-                match (key, pressed) {
-                    (PhysicalKey::Code(code), true) => ctx.input.keyboard.set_key(code),
-                    (PhysicalKey::Code(code), false) => ctx.input.keyboard.release_key(code),
-                    (PhysicalKey::Unidentified(code), _) => {
-                        log::error!("pressed/released unidentified key {:?}", code)
+                    if event.state.is_pressed() {
+                        match &event.logical_key {
+                            Key::Named(NamedKey::Backspace) => {
+                                ctx.input.text.edit(TextEdit::Backspace)
+                            }
+                            Key::Named(NamedKey::Delete) => ctx.input.text.edit(TextEdit::Delete),
+                            Key::Named(NamedKey::Enter) => ctx.input.text.edit(TextEdit::Submit),
+                            Key::Character(c)
+                                if c.eq_ignore_ascii_case("v")
+                                    && input::is_paste_shortcut(
+                                        input::modifier_pressed(&ctx, KeyModifier::Ctrl),
+                                        input::modifier_pressed(&ctx, KeyModifier::Alt),
+                                        input::modifier_pressed(&ctx, KeyModifier::Super),
+                                    ) =>
+                            {
+                                ctx.input.paste()
+                            }
+                            // Shortcuts are not typing
+                            _ if input::is_shortcut(
+                                input::modifier_pressed(&ctx, KeyModifier::Ctrl),
+                                input::modifier_pressed(&ctx, KeyModifier::Alt),
+                            ) => {}
+                            _ => {
+                                if let Some(text) = &event.text {
+                                    ctx.input.type_text(text);
+                                }
+                            }
+                        }
                     }
-                };
+                }
+                WindowEvent::Ime(ime) => match ime {
+                    Ime::Preedit(text, cursor) => ctx.input.text.set_preedit(text, *cursor),
+                    Ime::Commit(text) => {
+                        ctx.input.text.set_preedit("", None);
+                        ctx.input.type_text(text);
+                    }
+                    Ime::Enabled | Ime::Disabled => ctx.input.text.set_preedit("", None),
+                },
+                _ => {}
             }
-            _ => {}
-        },
+        }
         Event::DeviceEvent { ref event, .. } => match event {
-            DeviceEvent::MouseMotion { delta } => ctx.input.mouse.set_mouse_delta(*delta),
+            DeviceEvent::MouseMotion { delta } => {
+                ctx.input.mouse.add_mouse_delta(*delta);
+                ctx.input.record(InputEventKind::MouseMotion {
+                    dx: delta.0,
+                    dy: delta.1,
+                });
+            }
             _ => {}
         },
+        Event::LoopExiting => {
+            app.callbacks.on_exit(&mut ctx);
+        }
         Event::AboutToWait => {
-            ctx.render.window.request_redraw();
+            // Wait until shortly before the next frame, then spin the rest
+            if let Some(deadline) = ctx.time.next_frame_deadline() {
+                if Instant::now() + FRAME_SPIN_THRESHOLD < deadline {
+                    target
+                        .set_control_flow(ControlFlow::WaitUntil(deadline - FRAME_SPIN_THRESHOLD));
+                    return;
+                }
+                time::sleep_until(deadline);
+            }
+            // Also leaves WaitUntil once the target fps is removed
+            target.set_control_flow(ControlFlow::Poll);
+
+            if let Some(window) = ctx.render.window() {
+                window.request_redraw();
+            }
         }
         _ => {}
     });
@@ -104,6 +252,83 @@ pub(crate) async fn run_window<C: Callbacks + 'static>(
     }
 }
 
+/// Cursor image drawn at canvas resolution, so it scales with the canvas
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CursorSprite {
+    pub width: u32,
+    pub height: u32,
+    /// RGBA pixels row by row, blended over the canvas
+    pub rgba: Vec<u8>,
+    /// Pixel of the sprite placed at the mouse position
+    pub hotspot: (u32, u32),
+}
+
+impl CursorSprite {
+    /// Load a cursor sprite from an image file
+    pub fn load(path: impl AsRef<Path>, hotspot: (u32, u32)) -> ImageResult<Self> {
+        let image = image::open(path)?.into_rgba8();
+        Ok(Self {
+            width: image.width(),
+            height: image.height(),
+            rgba: image.into_raw(),
+            hotspot,
+        })
+    }
+
+    /// Draw with the hotspot at x, y, skipping pixels outside the canvas
+    fn draw(&self, canvas: &mut Canvas, x: u32, y: u32) {
+        let pixels = self
+            .rgba
+            .chunks_exact(4)
+            .take((self.width * self.height) as usize);
+        for (i, color) in pixels.enumerate() {
+            let pixel_x = (x + i as u32 % self.width).checked_sub(self.hotspot.0);
+            let pixel_y = (y + i as u32 / self.width).checked_sub(self.hotspot.1);
+            if let (Some(pixel_x), Some(pixel_y)) = (pixel_x, pixel_y) {
+                if pixel_x < canvas.width && pixel_y < canvas.height {
+                    canvas.write_pixel_blend(
+                        pixel_x,
+                        pixel_y,
+                        &[color[0], color[1], color[2], color[3]],
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Draw the cursor sprite on the overlay while the mouse is over the window
+///
+/// Skipped when presenting to a terminal
+///
+/// Called after the user render
+pub(crate) fn draw_cursor(ctx: &mut Context) {
+    // Terminals have no mouse to follow
+    if ctx.render.window().is_some() {
+        draw_cursor_sprite(ctx);
+    }
+}
+
+/// Draw the cursor sprite on the overlay, which is never exported in screenshots
+fn draw_cursor_sprite(ctx: &mut Context) {
+    if !input::mouse_on_screen(ctx) {
+        return;
+    }
+    let Some(sprite) = ctx.render.cursor.take() else {
+        return;
+    };
+    let (x, y) = input::mouse_pos_pixel(ctx);
+    sprite.draw(ctx.render.overlay_canvas(), x, y);
+    ctx.render.cursor = Some(sprite);
+}
+
+/// Show the system cursor only if it is visible and not replaced by a sprite
+fn update_cursor_visible(ctx: &Context) {
+    if let Some(window) = ctx.render.window() {
+        window.set_cursor_visible(ctx.render.cursor_visible && ctx.render.cursor.is_none());
+    }
+}
+
 //
 // Commands
 //
@@ -111,11 +336,17 @@ pub(crate) async fn run_window<C: Callbacks + 'static>(
 /// Reference to the window
 ///
 /// Window contains useful functions such as fullscreen, cursor control and resizing window
+///
+/// Panics if presenting to a terminal
 pub fn window_ref(ctx: &mut Context) -> &winit::window::Window {
-    &ctx.render.window
+    ctx.render
+        .window()
+        .expect("no window when presenting to a terminal")
 }
 
 /// Enable/Disable vsync
+///
+/// Does nothing when presenting to a terminal
 pub fn set_vsync(ctx: &mut Context, vsync: bool) {
     let present_mode = if vsync {
         wgpu::PresentMode::AutoVsync
@@ -123,4 +354,131 @@ pub fn set_vsync(ctx: &mut Context, vsync: bool) {
         wgpu::PresentMode::AutoNoVsync
     };
     ctx.render.reconfigure_present_mode(present_mode);
+    ctx.config.vsync = vsync;
+}
+
+/// Allow input methods to compose text, e.g. for Chinese or Japanese
+///
+/// Composed text is received through input::ime_preedit and input::text_input.
+/// Does nothing when presenting to a terminal
+pub fn set_ime_allowed(ctx: &mut Context, allowed: bool) {
+    if let Some(window) = ctx.render.window() {
+        window.set_ime_allowed(allowed);
+    }
+}
+
+/// Where text is being typed in canvas pixels, so the input method popup can avoid it
+///
+/// Does nothing when presenting to a terminal
+pub fn set_ime_cursor_area(ctx: &mut Context, x: u32, y: u32, width: u32, height: u32) {
+    let (window_width, window_height) = ctx.render.window_size();
+    let scale_x = window_width as f64 / ctx.render.canvas.width as f64;
+    let scale_y = window_height as f64 / ctx.render.canvas.height as f64;
+    if let Some(window) = ctx.render.window() {
+        window.set_ime_cursor_area(
+            winit::dpi::PhysicalPosition::new(x as f64 * scale_x, y as f64 * scale_y),
+            winit::dpi::PhysicalSize::new(width as f64 * scale_x, height as f64 * scale_y),
+        );
+    }
+}
+
+/// Shows or hides the system cursor while it is over the window
+///
+/// Does nothing when presenting to a terminal
+pub fn set_cursor_visible(ctx: &mut Context, visible: bool) {
+    ctx.render.cursor_visible = visible;
+    update_cursor_visible(ctx);
+}
+
+/// Locks the cursor in place, e.g. for mouse look
+///
+/// Hide the cursor as well for relative mouse input, movement is still reported
+/// by input::mouse_delta. Where locking is unsupported the cursor is confined to the window.
+/// Does nothing when presenting to a terminal
+pub fn set_cursor_locked(ctx: &mut Context, locked: bool) {
+    let Some(window) = ctx.render.window() else {
+        return;
+    };
+    let result = if locked {
+        window
+            .set_cursor_grab(CursorGrabMode::Locked)
+            .or_else(|_| window.set_cursor_grab(CursorGrabMode::Confined))
+    } else {
+        window.set_cursor_grab(CursorGrabMode::None)
+    };
+    if let Err(err) = result {
+        log::error!("could not grab cursor: {err}");
+    }
+}
+
+/// Replaces the system cursor with a sprite drawn over the canvas, None restores it
+///
+/// The sprite is drawn on top of the presented frame, leaving the pixel buffer untouched,
+/// and is left out of screenshots. Does nothing when presenting to a terminal
+pub fn set_cursor_sprite(ctx: &mut Context, sprite: Option<CursorSprite>) {
+    ctx.render.cursor = sprite;
+    update_cursor_visible(ctx);
+}
+
+//
+// Tests
+//
+
+#[cfg(test)]
+mod tests {
+    use super::{draw_cursor, draw_cursor_sprite, set_cursor_sprite, CursorSprite};
+    use crate::{debug, Context};
+
+    #[test]
+    fn test_cursor_sprite() {
+        let mut ctx = Context::headless(4, 4);
+        ctx.input.mouse.set_on_screen(true);
+        ctx.input.mouse.set_pos(0.0, 1.0);
+
+        // Two pixels wide with the hotspot on the right, the left pixel transparent
+        let sprite = CursorSprite {
+            width: 2,
+            height: 1,
+            rgba: vec![255, 0, 0, 0, 0, 255, 0, 255],
+            hotspot: (1, 0),
+        };
+        set_cursor_sprite(&mut ctx, Some(sprite));
+        draw_cursor_sprite(&mut ctx);
+        ctx.render.finish_frame();
+
+        let overlay = ctx.render.overlay.as_ref().unwrap();
+        assert_eq!(overlay.pixel_rgb(0, 1), [0, 255, 0]);
+        assert_eq!(ctx.render.canvas.pixel_rgb(0, 1), [0, 0, 0]);
+
+        // Hidden while the mouse is outside the window
+        ctx.input.mouse.set_on_screen(false);
+        draw_cursor_sprite(&mut ctx);
+        ctx.render.finish_frame();
+        assert!(ctx.render.overlay.is_none());
+    }
+
+    #[test]
+    fn test_cursor_not_exported_or_drawn_in_terminal() {
+        let mut ctx = Context::headless(4, 4);
+        ctx.input.mouse.set_on_screen(true);
+        ctx.input.mouse.set_pos(0.0, 1.0);
+        let sprite = CursorSprite {
+            width: 2,
+            height: 1,
+            rgba: vec![255, 0, 0, 0, 0, 255, 0, 255],
+            hotspot: (1, 0),
+        };
+        set_cursor_sprite(&mut ctx, Some(sprite));
+        debug::set_overlay_enabled(&mut ctx, true);
+        debug::set_overlay_in_screenshots(&mut ctx, true);
+        draw_cursor_sprite(&mut ctx);
+
+        assert_ne!(debug::screenshot_canvas(&ctx).pixel_rgb(0, 1), [0, 255, 0]);
+
+        // The headless context presents to a terminal
+        ctx.render.finish_frame();
+        draw_cursor(&mut ctx);
+        ctx.render.finish_frame();
+        assert!(ctx.render.overlay.is_none());
+    }
 }